@@ -83,7 +83,7 @@ impl Rgb {
 
 impl Display for Rgb {
 	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-		write!(f, "#{:6X}", self.rgb())
+		write!(f, "#{:06X}", self.rgb())
 	}
 }
 
@@ -189,7 +189,7 @@ impl Rgba {
 
 impl Display for Rgba {
 	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-		write!(f, "#{:8X}", self.rgba())
+		write!(f, "#{:08X}", self.rgba())
 	}
 }
 
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::collections::hash_map::Iter;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Directory<V> {
 	root: Node<V>
 }
@@ -33,6 +33,32 @@ impl<V> Directory<V> {
 		&self.root
 	}
 	
+	/// Returns every value in the directory along with its full key, sorted by key.
+	pub fn entries(&self) -> Vec<(String, &V)> {
+		let mut entries = Vec::new();
+		
+		if let Some(iter) = self.root.iter() {
+			for (part, node) in iter {
+				Self::entries_helper(node, part.clone(), &mut entries);
+			}
+		}
+		
+		entries.sort_by(|a, b| a.0.cmp(&b.0));
+		entries
+	}
+	
+	fn entries_helper<'a>(node: &'a Node<V>, key: String, entries: &mut Vec<(String, &'a V)>) {
+		if let Some(iter) = node.iter() {
+			for (part, deeper) in iter {
+				Self::entries_helper(deeper, format!("{}.{}", key, part), entries);
+			}
+		}
+		
+		if let Some(value) = node.get() {
+			entries.push((key, value));
+		}
+	}
+	
 	// Avoid pissing off the borrow checker
 	fn insert_helper<'a, I>(node: &mut Node<V>, mut current: I, value: V) where I: Iterator<Item=&'a str> {
 		if let Some(part) =  current.next() {
@@ -49,7 +75,7 @@ impl<V> Directory<V> {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Node<V> {
 	branch: Option<HashMap<String, Node<V>>>,
 	leaf: Option<V>
@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate serde_derive;
 
+#[macro_use]
 extern crate serde;
 extern crate serde_json;
 extern crate glutin;
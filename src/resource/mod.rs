@@ -24,6 +24,13 @@ pub struct DirAssetSource {
 	path: PathBuf
 }
 
+impl DirAssetSource {
+	/// Creates a source that looks up assets relative to the directory, such as `assets/minecraft`.
+	pub fn new<P>(path: P) -> Self where P: Into<PathBuf> {
+		DirAssetSource { path: path.into() }
+	}
+}
+
 impl<A: Asset> AssetSource<A> for DirAssetSource {
 	type Ticket = File;
 	
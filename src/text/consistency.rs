@@ -0,0 +1,180 @@
+use text::language::{Directory, Compiled, Part, LoadError};
+use std::collections::BTreeMap;
+use std::fmt::{self, Formatter, Display};
+
+/// A difference between a translation and the reference translation of the same key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+	/// The key is in the reference locale, but not in the translated one.
+	Missing,
+	/// The key is in the translated locale, but not in the reference one.
+	Extra,
+	/// The translation could not be loaded, so it could not be compared.
+	Invalid(LoadError),
+	/// Both translations refer to the same arguments, but a different number of times.
+	PlaceholderCount { reference: usize, translated: usize },
+	/// The translations refer to different arguments. The indexes are sorted and count from 0.
+	PlaceholderIndexes { reference: Vec<usize>, translated: Vec<usize> },
+	/// Only one of the translations converts the argument to upper case, such as %S instead of %s.
+	Uppercase { index: usize, reference: bool },
+	/// Only one of the translations contains § formatting codes.
+	FormattingCodes { reference: bool }
+}
+
+impl Display for Problem {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			Problem::Missing => write!(f, "missing from the translation"),
+			Problem::Extra => write!(f, "not in the reference locale"),
+			Problem::Invalid(ref err) => write!(f, "the translation could not be loaded: {}", err.error()),
+			Problem::PlaceholderCount { reference, translated } => write!(f, "the reference has {} placeholders, but the translation has {}", reference, translated),
+			Problem::PlaceholderIndexes { ref reference, ref translated } => write!(f, "the reference refers to arguments {}, but the translation refers to arguments {}", Indexes(reference), Indexes(translated)),
+			Problem::Uppercase { index, reference: true } => write!(f, "argument {} is upper case in the reference, but not in the translation", index + 1),
+			Problem::Uppercase { index, reference: false } => write!(f, "argument {} is upper case in the translation, but not in the reference", index + 1),
+			Problem::FormattingCodes { reference: true } => write!(f, "the reference contains \u{A7} formatting codes, but the translation does not"),
+			Problem::FormattingCodes { reference: false } => write!(f, "the translation contains \u{A7} formatting codes, but the reference does not")
+		}
+	}
+}
+
+/// Displays argument indexes like they are written in format codes, counting from 1.
+struct Indexes<'a>(&'a [usize]);
+
+impl<'a> Display for Indexes<'a> {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		write!(f, "[")?;
+		
+		for (i, index) in self.0.iter().enumerate() {
+			if i != 0 { write!(f, ", ")? };
+			write!(f, "{}", index + 1)?;
+		}
+		
+		write!(f, "]")
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inconsistency {
+	pub key: String,
+	pub problem: Problem
+}
+
+impl Display for Inconsistency {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		write!(f, "{}: {}", self.key, self.problem)
+	}
+}
+
+/// Compares a translated locale against the reference locale, usually `en_US`, sorted by key.
+/// Translations that refer to arguments the game does not provide crash the vanilla client, and other differences are usually mistakes of the translator.
+/// Translations that could not be loaded in the reference locale are not compared.
+pub fn compare(reference: &Directory, translated: &Directory) -> Vec<Inconsistency> {
+	let mut inconsistencies = Vec::new();
+	
+	{
+		let mut report = |key: &str, problem| inconsistencies.push(Inconsistency { key: key.to_owned(), problem: problem });
+		
+		for (key, expected) in reference.entries() {
+			let actual = match translated.get(&key) {
+				Some(actual) => actual,
+				None => { report(&key, Problem::Missing); continue; }
+			};
+			
+			match (expected, actual) {
+				(&Ok(ref expected), &Ok(ref actual)) => for problem in compare_translation(expected, actual) {
+					report(&key, problem);
+				},
+				(&Ok(_), &Err(ref err)) => report(&key, Problem::Invalid(err.clone())),
+				(&Err(_), _) => ()
+			}
+		}
+		
+		for (key, _) in translated.entries() {
+			if reference.get(&key).is_none() {
+				report(&key, Problem::Extra);
+			}
+		}
+	}
+	
+	inconsistencies.sort_by(|a, b| a.key.cmp(&b.key));
+	inconsistencies
+}
+
+/// How a translation uses each argument: the number of placeholders, and whether any of them converts to upper case.
+struct Placeholders {
+	count: usize,
+	upper: BTreeMap<usize, bool>,
+	formatting: bool
+}
+
+impl Placeholders {
+	fn new(compiled: &Compiled) -> Self {
+		let mut placeholders = Placeholders { count: 0, upper: BTreeMap::new(), formatting: false };
+		
+		for part in compiled.parts() {
+			match part {
+				Part::Text(text) => placeholders.formatting |= text.contains('\u{A7}'),
+				Part::Arg { index, upper, .. } => {
+					placeholders.count += 1;
+					*placeholders.upper.entry(index).or_insert(false) |= upper;
+				}
+			}
+		}
+		
+		placeholders
+	}
+	
+	fn indexes(&self) -> Vec<usize> {
+		self.upper.keys().cloned().collect()
+	}
+}
+
+fn compare_translation(reference: &Compiled, translated: &Compiled) -> Vec<Problem> {
+	let expected = Placeholders::new(reference);
+	let actual = Placeholders::new(translated);
+	
+	let mut problems = Vec::new();
+	
+	if expected.indexes() != actual.indexes() {
+		problems.push(Problem::PlaceholderIndexes { reference: expected.indexes(), translated: actual.indexes() });
+	} else if expected.count != actual.count {
+		problems.push(Problem::PlaceholderCount { reference: expected.count, translated: actual.count });
+	}
+	
+	for (&index, &upper) in &expected.upper {
+		if actual.upper.get(&index).map(|&other| other != upper).unwrap_or(false) {
+			problems.push(Problem::Uppercase { index: index, reference: upper });
+		}
+	}
+	
+	if expected.formatting != actual.formatting {
+		problems.push(Problem::FormattingCodes { reference: expected.formatting });
+	}
+	
+	problems
+}
+
+#[test]
+fn test_compare() {
+	use text::language;
+	
+	let reference = language::load_lang("a=%s and %s\nb=%1$s\nc=%s\nd=\u{A7}lBold\ne=Same %%\nmissing=x\n", "en_US.lang").unwrap();
+	let translated = language::load_lang("a=%2$s und %1$s\nb=%1$s %1$s\nc=%s %s\nd=Fett\ne=%2$s\nextra=y\n", "de_DE.lang").unwrap();
+	
+	assert_eq!(compare(&reference, &translated), vec![
+		Inconsistency { key: "b".to_owned(), problem: Problem::PlaceholderCount { reference: 1, translated: 2 } },
+		Inconsistency { key: "c".to_owned(), problem: Problem::PlaceholderIndexes { reference: vec![0], translated: vec![0, 1] } },
+		Inconsistency { key: "d".to_owned(), problem: Problem::FormattingCodes { reference: true } },
+		Inconsistency { key: "e".to_owned(), problem: Problem::PlaceholderIndexes { reference: vec![], translated: vec![1] } },
+		Inconsistency { key: "extra".to_owned(), problem: Problem::Extra },
+		Inconsistency { key: "missing".to_owned(), problem: Problem::Missing }
+	]);
+	
+	let reference = language::load_lang("a=%s %s\n", "en_US.lang").unwrap();
+	let upper = language::load_lang("a=%1$S %2$s\n", "de_DE.lang").unwrap();
+	let problems = compare(&reference, &upper);
+	
+	assert_eq!(problems.len(), 1);
+	assert_eq!(problems[0].problem, Problem::Uppercase { index: 0, reference: false });
+	assert_eq!(problems[0].to_string(), "a: argument 1 is upper case in the translation, but not in the reference");
+}
@@ -1,4 +1,5 @@
-mod transform;
+pub mod transform;
+pub mod time;
 use std::fmt::{self, Formatter, Display};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -24,7 +25,8 @@ pub enum Kind {
 }
 
 impl Kind {
-	fn from_character(character: char, other: Option<char>) -> Option<(Self, bool)> {
+	/// Returns the kind for the conversion character, and whether it is upper case. Time conversions also need the character after it.
+	pub fn from_character(character: char, other: Option<char>) -> Option<(Self, bool)> {
 		Some(match character {
 			'b' => (Kind::Bool, 		false),
 			'B' => (Kind::Bool, 		true),
@@ -72,13 +74,31 @@ impl Kind {
 		}
 	}
 	
-	fn second_character(&self) -> Option<char> {
+	/// Returns the character after the conversion character, which is only used by time conversions.
+	pub fn second_character(&self) -> Option<char> {
 		match *self {
 			Kind::Time(tk) => Some(tk.character()),
 			_ => None
 		}
 	}
 	
+	/// Returns whether some argument type can be formatted by both kinds, such as an integer for %c and %x.
+	/// Bool, hash code and string conversions accept any argument, and ones without an argument conflict with nothing.
+	pub fn shares_argument_type(&self, other: Kind) -> bool {
+		self.argument_types() & other.argument_types() != 0
+	}
+	
+	/// Returns the set of argument types accepted by the kind: characters, integers up to int, longs, big integers, floating point numbers, and dates.
+	fn argument_types(&self) -> u8 {
+		match *self {
+			Kind::Bool | Kind::HexHashCode | Kind::String | Kind::Percent | Kind::Newline => 0b111111,
+			Kind::Unicode => 0b000011,
+			Kind::Decimal | Kind::Octal | Kind::Hex => 0b001110,
+			Kind::CompSciNot | Kind::Float | Kind::SciNot | Kind::Hexfloat => 0b010000,
+			Kind::Time(_) => 0b100100
+		}
+	}
+	
 	fn honors_uppercase(&self) -> bool {
 		match *self {
 			Kind::Bool 			=> true,
@@ -173,7 +193,7 @@ pub enum Flag {
 	LeadingSpace,
 	/// Numeric: Indicates to pad the output with zeroes. Width must be provided. Mutually exclusive with LeftJustify.
 	ZeroPad,
-	/// [Decimal, SciNot, Float, CompSciNot]: Indicates to use a locale-specific grouping seperator. (',' in en_US to format the number 10000 as 10,000)
+	/// [Decimal, SciNot, Float]: Indicates to use a locale-specific grouping seperator. (',' in en_US to format the number 10000 as 10,000)
 	Group,
 	/// [Decimal, SciNot, Float, CompSciNot]: Indicates to surround the value with '(' and ')' if it is negative.
 	Parentheses,
@@ -248,6 +268,16 @@ impl Flags {
 		self.0 != 0
 	}
 	
+	/// Returns the flags packed into a byte, with one bit per flag.
+	pub fn bits(&self) -> u8 {
+		self.0
+	}
+	
+	/// Unpacks flags that were packed by `bits`, ignoring unknown bits.
+	pub fn from_bits(bits: u8) -> Self {
+		Flags(bits & 127)
+	}
+	
 	pub fn left_justify(&self) -> bool {
 		self.0 & Flag::LeftJustify.bit() != 0
 	}
@@ -333,9 +363,17 @@ impl FormatCommand {
 		}
 		
 		// Get the first number, which may either be the argument index or the width. If there is not number here, then it was either flags, start of precision, or the conversion.
-		let (num, s) = get_num(&s[1..]);
+		let after_percent = &s[1..];
+		let (num, s) = get_num(after_percent);
 		let was_index = s.starts_with('$');
 		
+		// A width never starts with a zero, so a leading zero that isn't part of an argument index is the zero padding flag, as in %08d.
+		let (num, s) = if !was_index && num.starts_with('0') {
+			("", after_percent)
+		} else {
+			(num, s)
+		};
+		
 		// Try to parse the numeric value.
 		let value = if !num.is_empty() {
 			Some(num.parse::<usize>().expect("parsing an integer from a digits-only string should never fail"))
@@ -413,6 +451,43 @@ impl FormatCommand {
 	}
 }
 
+/// Writes the format command back in the form that it was parsed from.
+impl Display for FormatCommand {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		write!(f, "%")?;
+		
+		if let Index::Exact(index) = self.index {
+			write!(f, "{}$", index)?;
+		}
+		
+		if self.index == Index::Previous {
+			write!(f, "{}", Flag::PreviousIndex.character())?;
+		}
+		
+		for &flag in &[Flag::LeftJustify, Flag::Alternate, Flag::Plus, Flag::LeadingSpace, Flag::ZeroPad, Flag::Group, Flag::Parentheses] {
+			if self.flags.0 & flag.bit() != 0 {
+				write!(f, "{}", flag.character())?;
+			}
+		}
+		
+		if let Some(width) = self.width {
+			write!(f, "{}", width)?;
+		}
+		
+		if let Some(precision) = self.precision {
+			write!(f, ".{}", precision)?;
+		}
+		
+		write!(f, "{}", self.kind.character(self.upper))?;
+		
+		if let Some(second) = self.kind.second_character() {
+			write!(f, "{}", second)?;
+		}
+		
+		Ok(())
+	}
+}
+
 fn get_num(s: &str) -> (&str, &str) {
 	let mut digits = 0;
 	
@@ -434,4 +509,6 @@ fn test_parse() {
 	assert_eq!(Ok((7, FormatCommand { index: Index::Exact(42), flags: Flags(0), width: Some(43), precision: None, kind: Kind::Decimal, upper: false})), FormatCommand::parse("%42$43d"));
 	assert_eq!(Ok((7, FormatCommand { index: Index::Next, flags: Flags(0), width: Some(42), precision: Some(43), kind: Kind::Float, upper: false})),    FormatCommand::parse("%42.43f"));
 	assert_eq!(Ok((10, FormatCommand { index: Index::Exact(41), flags: Flags(0), width: Some(42), precision: Some(43), kind: Kind::Float, upper: false})),FormatCommand::parse("%41$42.43f"));
+	assert_eq!(Ok((4, FormatCommand { index: Index::Next, flags: Flags(16), width: Some(8), precision: None, kind: Kind::Decimal, upper: false})),       FormatCommand::parse("%08d"));
+	assert_eq!(Ok((8, FormatCommand { index: Index::Exact(2), flags: Flags(48), width: Some(10), precision: None, kind: Kind::Decimal, upper: false})), FormatCommand::parse("%2$0,10d"));
 }
\ No newline at end of file
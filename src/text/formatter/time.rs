@@ -0,0 +1,119 @@
+use text::formatter::TimeKind;
+
+const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// A time zone with a fixed offset from UTC. There is no time zone database to look up daylight saving time, so the caller picks the offset.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Zone {
+	offset: i32,
+	abbreviation: String
+}
+
+impl Zone {
+	pub fn utc() -> Self {
+		Zone::new(0, "UTC")
+	}
+	
+	/// Creates a zone that is `offset` seconds ahead of UTC, such as 3600 for CET. The abbreviation is written by %tZ.
+	pub fn new(offset: i32, abbreviation: &str) -> Self {
+		Zone { offset: offset, abbreviation: abbreviation.to_owned() }
+	}
+	
+	/// The amount of seconds that the zone is ahead of UTC.
+	pub fn offset(&self) -> i32 {
+		self.offset
+	}
+	
+	pub fn abbreviation(&self) -> &str {
+		&self.abbreviation
+	}
+}
+
+/// Formats a time given in milliseconds since the Unix epoch like Java's %t does for a Long argument.
+/// Upper case variants, such as %Tp, are handled by converting the result to upper case.
+pub fn format(kind: TimeKind, millis: i64, zone: &Zone) -> String {
+	// Times before the epoch still count forward from midnight. The offset is added after the remainder to avoid overflow.
+	let of_day = ((millis % MILLIS_PER_DAY) + zone.offset as i64 * 1000 % MILLIS_PER_DAY + 2 * MILLIS_PER_DAY) % MILLIS_PER_DAY;
+	
+	let hour = of_day / (60 * 60 * 1000);
+	let minute = of_day / (60 * 1000) % 60;
+	let second = of_day / 1000 % 60;
+	let milli = of_day % 1000;
+	
+	// The 12 hour clock goes from 12 to 11, as midnight and noon are 12.
+	let hour12 = if hour % 12 == 0 { 12 } else { hour % 12 };
+	
+	match kind {
+		TimeKind::Hour24 => format!("{:02}", hour),
+		TimeKind::Hour12 => format!("{:02}", hour12),
+		TimeKind::UnpaddedHour24 => hour.to_string(),
+		TimeKind::UnpaddedHour12 => hour12.to_string(),
+		TimeKind::Minute => format!("{:02}", minute),
+		TimeKind::Second => format!("{:02}", second),
+		TimeKind::Milli => format!("{:03}", milli),
+		// Only milliseconds are known, so the remaining digits are always zero.
+		TimeKind::Nano => format!("{:09}", milli * 1000000),
+		TimeKind::Marker => if hour < 12 { "am" } else { "pm" }.to_owned(),
+		TimeKind::TzOffset => {
+			// Like Java, seconds of the offset are ignored.
+			let minutes = zone.offset.abs() / 60;
+			
+			format!("{}{:02}{:02}", if zone.offset < 0 {'-'} else {'+'}, minutes / 60, minutes % 60)
+		},
+		TimeKind::TzAbbrev => zone.abbreviation.clone(),
+		// Java divides towards zero, so the second before the epoch is 0 instead of -1.
+		TimeKind::EpochSecond => (millis / 1000).to_string(),
+		TimeKind::EpochMilli => millis.to_string()
+	}
+}
+
+#[test]
+fn test_time() {
+	use text::formatter::FormatCommand;
+	use text::formatter::transform::{Target, Prim};
+	use text::locale::NumberFormat;
+	
+	let format = |code: &str, millis: i64, zone: &Zone| {
+		let (_, cmd) = FormatCommand::parse(code).unwrap();
+		Target::from(&cmd).unwrap().format_in(&Prim::Integer(millis), cmd.upper, &NumberFormat::en_us(), zone).unwrap()
+	};
+	
+	let utc = Zone::utc();
+	let india = Zone::new(5 * 3600 + 30 * 60, "GMT+05:30");
+	let pacific = Zone::new(-8 * 3600, "GMT-08:00");
+	
+	// Generated with String.format(Locale.US, code, millis) on Java 17, with the default time zone set to the zone.
+	let table = [
+		("%tH", 1500000000123, &utc, "02"),
+		("%tI", 0, &utc, "12"),
+		("%tk", 43200000, &utc, "12"),
+		("%tl", -1, &utc, "11"),
+		("%tM", 1700000000999, &utc, "13"),
+		("%tS", 1700000000999, &utc, "20"),
+		("%tL", 1500000000123, &utc, "123"),
+		("%tN", -1, &utc, "999000000"),
+		("%tp", 0, &utc, "am"),
+		("%Tp", 43200000, &utc, "PM"),
+		("%tz", 0, &utc, "+0000"),
+		("%tZ", 0, &utc, "UTC"),
+		("%ts", -1, &utc, "0"),
+		("%ts", -86400001, &utc, "-86400"),
+		("%tQ", -86400001, &utc, "-86400001"),
+		("%tH", 0, &india, "05"),
+		("%tM", -1, &india, "29"),
+		("%tp", 43200000, &india, "pm"),
+		("%tz", 0, &india, "+0530"),
+		("%TZ", 0, &india, "GMT+05:30"),
+		("%tH", 1500000000123, &pacific, "18"),
+		("%tI", 1700000000999, &pacific, "02"),
+		("%tz", 0, &pacific, "-0800"),
+		("%-6tH", 0, &pacific, "16    "),
+		("%4tl", 43200000, &pacific, "   4")
+	];
+	
+	for &(code, millis, zone, expected) in table.iter() {
+		assert_eq!(format(code, millis, zone), expected, "formatting {} with {} in {:?}", millis, code, zone);
+	}
+	
+	assert_eq!(format("%tz", 0, &Zone::new(-30 * 60, "GMT-00:30")), "-0030");
+}
@@ -1,7 +1,13 @@
-use text::formatter::{FormatCommand, Kind, Flag};
+use text::formatter::{FormatCommand, Kind, Flag, TimeKind};
+use text::formatter::time::{self, Zone};
+use text::locale::NumberFormat;
+use serde_json::Value;
+use std::fmt::{self, Formatter, Display};
+use std::iter;
 
+/// A primitive value given to a format code.
 #[derive(Debug, Clone, PartialEq)]
-enum Prim {
+pub enum Prim {
 	Bool(bool),
 	Null,
 	Undefined,
@@ -10,7 +16,75 @@ enum Prim {
 	String(String)
 }
 
-enum PaddingStrategy {
+impl Prim {
+	/// Reads a primitive from the text of an argument. Numbers are often passed to translations as text, so
+	/// anything that looks like a number becomes an Integer or a Float, and everything else is a String.
+	pub fn parse(text: &str) -> Prim {
+		let numeric = !text.is_empty() && text.chars().all(|c| c.is_digit(10) || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E');
+		
+		if !numeric {
+			Prim::String(text.to_owned())
+		} else if let Ok(integer) = text.parse::<i64>() {
+			Prim::Integer(integer)
+		} else if let Ok(float) = text.parse::<f64>() {
+			Prim::Float(float)
+		} else {
+			Prim::String(text.to_owned())
+		}
+	}
+	
+	/// Converts an element of the `with` array of a chat component. Strings stay strings, even if they look like numbers.
+	/// Returns None for arrays and objects, which are chat components instead of primitives.
+	pub fn from_json(value: &Value) -> Option<Prim> {
+		Some(match *value {
+			Value::Bool(b) => Prim::Bool(b),
+			Value::Null => Prim::Null,
+			Value::Number(ref number) => match number.as_i64() {
+				Some(integer) => Prim::Integer(integer),
+				// Integers too large for an i64 lose precision, like they do in Java's JSON libraries.
+				None => Prim::Float(number.as_f64().unwrap_or(::std::f64::NAN))
+			},
+			Value::String(ref string) => Prim::String(string.clone()),
+			Value::Array(_) | Value::Object(_) => return None
+		})
+	}
+	
+	/// Returns the result of Java's `hashCode` for the boxed value. Integers that fit in an int hash like `Integer`, and others hash like `Long`.
+	/// Null has no hash code.
+	pub fn java_hash(&self) -> Option<i32> {
+		Some(match *self {
+			Prim::Bool(true) => 1231,
+			Prim::Bool(false) => 1237,
+			Prim::Null | Prim::Undefined => return None,
+			Prim::Integer(i) if i >= ::std::i32::MIN as i64 && i <= ::std::i32::MAX as i64 => i as i32,
+			Prim::Integer(i) => (i ^ ((i as u64) >> 32) as i64) as i32,
+			Prim::Float(v) => {
+				// Java hashes every NaN the same way, using the canonical NaN.
+				let bits = if v.is_nan() { 0x7ff8000000000000 } else { v.to_bits() };
+				(bits ^ (bits >> 32)) as i32
+			},
+			// Strings hash the UTF-16 code units.
+			Prim::String(ref string) => string.encode_utf16().fold(0i32, |hash, unit| hash.wrapping_mul(31).wrapping_add(unit as i32))
+		})
+	}
+}
+
+/// Formats a primitive the way Java's `String.valueOf` does.
+impl Display for Prim {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			Prim::Bool(b) => write!(f, "{}", b),
+			Prim::Null => write!(f, "null"),
+			Prim::Undefined => write!(f, "undefined"),
+			Prim::Integer(i) => write!(f, "{}", i),
+			Prim::Float(v) => write!(f, "{}", java_double(v)),
+			Prim::String(ref s) => write!(f, "{}", s)
+		}
+	}
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PaddingStrategy {
 	/// [  -123]
 	RightAlign,
 	/// [-123  ]
@@ -19,7 +93,8 @@ enum PaddingStrategy {
 	ZeroPad
 }
 
-enum Sign {
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Sign {
 	/// For negative numbers: "-num". For positive numbers: "num"
 	Minus,
 	/// For negative numbers: "(num)". For positive numbers: "num"
@@ -30,12 +105,15 @@ enum Sign {
 	PositiveSpace
 }
 
-struct NumericRep {
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct NumericRep {
 	sign: Sign,
 	group: bool
 }
 
-struct Target {
+/// How a format code turns a primitive into text: the conversion, along with the width, precision and flags.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Target {
 	min_width: usize,
 	precision: Option<usize>,
 	strategy: PaddingStrategy,
@@ -43,17 +121,53 @@ struct Target {
 	trans: Trans
 }
 
-enum Error {
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Error {
 	ConflictingFlags(Flag, Flag),
-	UnsupportedKind(Kind),
 	NoAlternate,
+	/// The flag can't be used with the kind of conversion, such as '+' with %s or ',' with %x.
+	FlagMismatch(Flag, Kind),
+	/// The kind of conversion does not take a precision, such as %.2d.
+	Precision(Kind),
+	/// The flag pads the result up to the width, but no width was given, such as %-s.
+	MissingWidth(Flag),
 	Escape
 }
 
+impl Error {
+	pub fn help(&self) -> Option<&'static str> {
+		match *self {
+			Error::ConflictingFlags(_, _) => None,
+			Error::NoAlternate => Some("only %o, %x, %e, %f and %a have an alternate form"),
+			Error::FlagMismatch(Flag::Plus, _) | Error::FlagMismatch(Flag::LeadingSpace, _) => Some("only %d and the floating point conversions (%e, %f, %g and %a) can show the sign of a positive number"),
+			Error::FlagMismatch(Flag::ZeroPad, _) => Some("only numbers can be padded with zeroes, leave out the '0' to pad with spaces"),
+			Error::FlagMismatch(Flag::Group, _) => Some("only %d, %f and %g can group digits"),
+			Error::FlagMismatch(Flag::Parentheses, _) => Some("only %d, %e, %f and %g can surround negative numbers with parentheses"),
+			Error::FlagMismatch(_, _) => None,
+			Error::Precision(_) => Some("precision is only allowed for %b, %h, %s and the floating point conversions, such as %.3s or %.2f"),
+			Error::MissingWidth(_) => Some("add a width after the flags, such as %-8s or %08d"),
+			Error::Escape => None
+		}
+	}
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			Error::ConflictingFlags(a, b) => write!(f, "flags {:?} ['{}'] and {:?} ['{}'] cannot be used together", a, a.character(), b, b.character()),
+			Error::NoAlternate => write!(f, "the alternate flag ('#') is not allowed for this conversion"),
+			Error::FlagMismatch(flag, kind) => write!(f, "flag {:?} ['{}'] is not allowed for kind {:?}", flag, flag.character(), kind),
+			Error::Precision(kind) => write!(f, "kind {:?} does not take a precision", kind),
+			Error::MissingWidth(flag) => write!(f, "flag {:?} ['{}'] requires a width", flag, flag.character()),
+			Error::Escape => write!(f, "escapes (%% and %n) do not have a target")
+		}
+	}
+}
+
 impl Target {
-	fn from_unchecked(cmd: &FormatCommand) -> Result<Self, Error> {
-		// TODO: Alternate
-		
+	/// Creates the target of a format command without rejecting the flags, width or precision that Java rejects.
+	/// Only use this for format commands that were already checked by `from`, such as ones read back from a cache.
+	pub fn from_unchecked(cmd: &FormatCommand) -> Result<Self, Error> {
 		Ok(Target {
 			min_width: cmd.width.unwrap_or(0),
 			precision: cmd.precision,
@@ -84,9 +198,13 @@ impl Target {
 			trans: match (cmd.kind, cmd.flags.alternate()) {
 				(Kind::Bool, false) => Trans::Bool,
 				(Kind::Bool, true) => return Err(Error::NoAlternate),
-				// TODO: Kind::HexHashCode: NoAlternate
-				// TODO: Kind::String: NoAlternate
-				// TODO: Kind::Character: NoAlternate
+				(Kind::HexHashCode, false) => Trans::HashCode,
+				(Kind::HexHashCode, true) => return Err(Error::NoAlternate),
+				// The alternate flag is only meaningful for java.util.Formattable arguments, which never show up here.
+				(Kind::String, false) => Trans::String,
+				(Kind::String, true) => return Err(Error::NoAlternate),
+				(Kind::Unicode, false) => Trans::Char,
+				(Kind::Unicode, true) => return Err(Error::NoAlternate),
 				(Kind::Decimal, false) => Trans::Decimal,
 				(Kind::Decimal, true) => return Err(Error::NoAlternate),
 				(Kind::Octal, flag) => Trans::Octal { has_radix: flag },
@@ -96,36 +214,193 @@ impl Target {
 				(Kind::Float, flag) => Trans::Float { force_decimal: flag },
 				(Kind::CompSciNot, flag) => Trans::CompSciNot { force_decimal: flag },
 				(Kind::Hexfloat, flag) => Trans::Hexfloat { force_decimal: flag },
-				// TODO: Time
+				(Kind::Time(kind), false) => Trans::Time(kind),
+				(Kind::Time(_), true) => return Err(Error::NoAlternate),
 				(Kind::Percent, _) => return Err(Error::Escape),
-				(Kind::Newline, _) => return Err(Error::Escape),
-				_ => return Err(Error::UnsupportedKind(cmd.kind))
+				(Kind::Newline, _) => return Err(Error::Escape)
 			}
 		})
 	}
 	
-	// TODO: precision for Kind::Bool, Kind::HexHashCode, Kind::String is max_chars
-	// TODO: precision for [all FP] is TODO
-	// TODO: precision for Kind::Unciode, Decimal, Octal, Hex is invalid
-	
-	
-	fn from(cmd: &FormatCommand) -> Result<Self, Error> {
+	/// Creates the target of a format command, rejecting the same combinations of flags, width and precision that Java's Formatter rejects.
+	pub fn from(cmd: &FormatCommand) -> Result<Self, Error> {
+		// The alternate flag is checked when picking the transformation.
 		let target = Self::from_unchecked(cmd)?;
 		
-		// check kind is Octal, Hex, [all FP] if Alt
-		// check kind is Decimal, [all FP] if Sign::PositivePlus or Sign::PositiveSpace
-		// check kind is [all Numeric] if PaddingStrategy::ZeroPad,
-		// check kind is Decimal, SciNot, Float, CompSciNot if group==true or Sign::Surround
-		// Kind::Octal / Kind::Hex: Reject sign if non Sign::Minus, reject if group==true.
-		// TODO: Floats and Doubles, Time
+		// LeftJustify is allowed for every kind, and Alternate has already been checked.
+		let allowed: &[Flag] = match cmd.kind {
+			Kind::Decimal | Kind::Float | Kind::SciNot => &[Flag::Plus, Flag::LeadingSpace, Flag::ZeroPad, Flag::Group, Flag::Parentheses],
+			Kind::CompSciNot => &[Flag::Plus, Flag::LeadingSpace, Flag::ZeroPad, Flag::Parentheses],
+			Kind::Hexfloat => &[Flag::Plus, Flag::LeadingSpace, Flag::ZeroPad],
+			// Java only allows signs and parentheses for BigInteger arguments, as other integers are written as unsigned.
+			Kind::Octal | Kind::Hex => &[Flag::ZeroPad],
+			_ => &[]
+		};
+		
+		let flags = [
+			(Flag::Plus, cmd.flags.plus()),
+			(Flag::LeadingSpace, cmd.flags.leading_space()),
+			(Flag::ZeroPad, cmd.flags.zero_pad()),
+			(Flag::Group, cmd.flags.group()),
+			(Flag::Parentheses, cmd.flags.parentheses())
+		];
+		
+		for &(flag, set) in flags.iter() {
+			if set && !allowed.contains(&flag) {
+				return Err(Error::FlagMismatch(flag, cmd.kind));
+			}
+		}
+		
+		match cmd.kind {
+			Kind::Unicode | Kind::Decimal | Kind::Octal | Kind::Hex | Kind::Time(_) if cmd.precision.is_some() => return Err(Error::Precision(cmd.kind)),
+			_ => ()
+		}
+		
+		if cmd.width.is_none() {
+			if cmd.flags.left_justify() {
+				return Err(Error::MissingWidth(Flag::LeftJustify));
+			} else if cmd.flags.zero_pad() {
+				return Err(Error::MissingWidth(Flag::ZeroPad));
+			}
+		}
 		
 		Ok(target)
 	}
+	
+	/// Returns true if the conversion copies the text of the argument, as %s does. 
+	/// Such arguments can keep their own styles, as only padding and truncation is applied to them.
+	pub fn is_text(&self) -> bool {
+		self.trans == Trans::String
+	}
+	
+	/// The maximum amount of characters of a textual argument to keep.
+	pub fn max_chars(&self) -> Option<usize> {
+		match self.trans {
+			Trans::Bool | Trans::String | Trans::HashCode => self.precision,
+			_ => None
+		}
+	}
+	
+	/// Returns the amount of spaces to insert before and after a value with the length of `chars` characters to reach the minimum width.
+	pub fn padding(&self, chars: usize) -> (usize, usize) {
+		let padding = self.min_width.saturating_sub(chars);
+		
+		match self.strategy {
+			PaddingStrategy::LeftAlign => (0, padding),
+			_ => (padding, 0)
+		}
+	}
+	
+	/// Formats the primitive, returning None if the conversion does not accept this kind of primitive (such as %d with text).
+	/// Numbers are written like en_US, and times are written in UTC.
+	pub fn format(&self, prim: &Prim, upper: bool) -> Option<String> {
+		self.format_in(prim, upper, &NumberFormat::en_us(), &Zone::utc())
+	}
+	
+	/// Formats the primitive like `format`, writing numbers with the separators of the number format and times in the zone.
+	/// Times are given in milliseconds since the Unix epoch.
+	pub fn format_in(&self, prim: &Prim, upper: bool, numbers: &NumberFormat, zone: &Zone) -> Option<String> {
+		let mut text = trans(self, prim, numbers, zone)?;
+		
+		if let Some(max) = self.max_chars() {
+			if let Some((end, _)) = text.char_indices().nth(max) {
+				text.truncate(end);
+			}
+		}
+		
+		if upper {
+			text = text.to_uppercase();
+		}
+		
+		let (before, after) = self.padding(text.chars().count());
+		
+		Some(iter::repeat(' ').take(before).chain(text.chars()).chain(iter::repeat(' ').take(after)).collect())
+	}
+	
+	/// Returns the text written before the magnitude of a number to show its sign.
+	fn sign_prefix(&self, negative: bool) -> &'static str {
+		match (negative, self.repr.sign) {
+			(true, Sign::Surround) => "(",
+			(true, _) => "-",
+			(false, Sign::PositivePlus) => "+",
+			(false, Sign::PositiveSpace) => " ",
+			(false, _) => ""
+		}
+	}
+	
+	/// Returns the text written after the magnitude of a number to show its sign.
+	fn sign_suffix(&self, negative: bool) -> &'static str {
+		if negative && self.repr.sign == Sign::Surround { ")" } else { "" }
+	}
+	
+	/// Surrounds the magnitude of a number with its sign, inserting zeroes in between if zero padding is enabled.
+	fn signed(&self, negative: bool, magnitude: &str) -> String {
+		let (prefix, suffix) = (self.sign_prefix(negative), self.sign_suffix(negative));
+		let mut out = String::with_capacity(self.min_width.max(magnitude.len() + 2));
+		
+		out.push_str(prefix);
+		
+		if self.strategy == PaddingStrategy::ZeroPad {
+			// Separators such as the no-break space take more than one byte.
+			let used = prefix.len() + magnitude.chars().count() + suffix.len();
+			out.extend(iter::repeat('0').take(self.min_width.saturating_sub(used)));
+		}
+		
+		out.push_str(magnitude);
+		out.push_str(suffix);
+		
+		out
+	}
+	
+	/// Joins the integer and fractional digits of a number, grouping the integer digits if enabled.
+	fn point(&self, integer: &str, fraction: &str, force_decimal: bool, numbers: &NumberFormat) -> String {
+		let mut out = self.group(integer, numbers);
+		
+		if !fraction.is_empty() || force_decimal {
+			out.push(numbers.decimal);
+		}
+		
+		out.push_str(fraction);
+		out
+	}
+	
+	/// Writes an unsigned number with a radix prefix, such as 0x, inserting zeroes in between if zero padding is enabled.
+	fn radix(&self, prefix: &str, digits: &str) -> String {
+		let mut out = prefix.to_owned();
+		
+		if self.strategy == PaddingStrategy::ZeroPad {
+			out.extend(iter::repeat('0').take(self.min_width.saturating_sub(prefix.len() + digits.len())));
+		}
+		
+		out.push_str(digits);
+		out
+	}
+	
+	fn group(&self, integer: &str, numbers: &NumberFormat) -> String {
+		if !self.repr.group || numbers.grouping_size == 0 {
+			return integer.to_owned();
+		}
+		
+		let mut out = String::with_capacity(integer.len() * 2);
+		
+		for (index, c) in integer.chars().enumerate() {
+			if index != 0 && (integer.len() - index) % numbers.grouping_size == 0 {
+				out.push(numbers.grouping);
+			}
+			
+			out.push(c);
+		}
+		
+		out
+	}
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum Trans {
 	Bool,
+	HashCode,
+	String,
+	Char,
 	// NUMERIC
 	Decimal,
 	Octal { has_radix: bool },
@@ -135,14 +410,736 @@ enum Trans {
 	Float { force_decimal: bool },
 	CompSciNot { force_decimal: bool },
 	Hexfloat { force_decimal: bool },
+	// TIME
+	Time(TimeKind)
+}
+
+impl Trans {
+	/// Returns true if the conversion only accepts numbers, including times given as a number.
+	fn is_numeric(&self) -> bool {
+		match *self {
+			Trans::Decimal | Trans::Octal { .. } | Trans::Hex { .. } | Trans::Time(_) => true,
+			trans => trans.is_floating()
+		}
+	}
+	
+	fn is_floating(&self) -> bool {
+		match *self {
+			Trans::SciNot | Trans::Float { .. } | Trans::CompSciNot { .. } | Trans::Hexfloat { .. } => true,
+			_ => false
+		}
+	}
+}
+
+/// Converts the primitive according to the conversion and numeric flags of the target, before truncation and justification.
+fn trans(target: &Target, prim: &Prim, numbers: &NumberFormat, zone: &Zone) -> Option<String> {
+	Some(match (target.trans, prim) {
+		(Trans::Bool, &Prim::Bool(b)) => b.to_string(),
+		(Trans::Bool, &Prim::Null) => "false".to_owned(),
+		(Trans::Bool, &Prim::Undefined) => "false".to_owned(),
+		(Trans::Bool, _) => "true".to_owned(),
+		(Trans::String, prim) => prim.to_string(),
+		// Like String.valueOf, every other conversion writes null as "null".
+		(_, &Prim::Null) | (_, &Prim::Undefined) => "null".to_owned(),
+		(Trans::HashCode, prim) => format!("{:x}", prim.java_hash().expect("only null has no hash code")),
+		// Java rejects code points outside of Unicode, and Rust has no way to represent a lone surrogate.
+		(Trans::Char, &Prim::Integer(i)) => if i < 0 || i > ::std::u32::MAX as i64 { return None } else { ::std::char::from_u32(i as u32)?.to_string() },
+		// A string of a single character is the closest thing to a Java Character.
+		(Trans::Char, &Prim::String(ref string)) if string.chars().count() == 1 => string.clone(),
+		// Numbers are often passed to translations as text.
+		(trans, &Prim::String(ref text)) if trans.is_numeric() => match Prim::parse(text) {
+			Prim::String(_) => return None,
+			number => return self::trans(target, &number, numbers, zone)
+		},
+		(Trans::Decimal, &Prim::Integer(i)) => {
+			// wrapping_abs keeps i64::MIN as is, which is still the correct magnitude once reinterpreted as unsigned.
+			target.signed(i < 0, &target.group(&(i.wrapping_abs() as u64).to_string(), numbers))
+		},
+		(Trans::Octal { has_radix }, &Prim::Integer(i)) => target.radix(if has_radix {"0"} else {""}, &format!("{:o}", i as u64)),
+		(Trans::Hex { has_radix }, &Prim::Integer(i)) => target.radix(if has_radix {"0x"} else {""}, &format!("{:x}", i as u64)),
+		// Java only accepts floating point values, but integers are common in translation arguments and convert without surprises.
+		(trans, &Prim::Integer(i)) if trans.is_floating() => float(target, i as f64, numbers),
+		(trans, &Prim::Float(v)) if trans.is_floating() => float(target, v, numbers),
+		(Trans::Time(kind), &Prim::Integer(millis)) => time::format(kind, millis, zone),
+		_ => return None
+	})
+}
+
+fn float(target: &Target, value: f64, numbers: &NumberFormat) -> String {
+	if value.is_nan() {
+		return "NaN".to_owned();
+	}
+	
+	// Like Java, -0.0 is negative.
+	let negative = value.is_sign_negative();
+	
+	if value.is_infinite() {
+		// Infinity gets a sign, but never zero padding.
+		let unpadded = Target { strategy: PaddingStrategy::RightAlign, .. *target };
+		return unpadded.signed(negative, "Infinity");
+	}
+	
+	let value = value.abs();
+	
+	match target.trans {
+		Trans::Float { force_decimal } => {
+			let (integer, fraction) = fixed(value, target.precision.unwrap_or(6));
+			
+			target.signed(negative, &target.point(&integer, &fraction, force_decimal, numbers))
+		},
+		Trans::CompSciNot { force_decimal } => {
+			let (integer, fraction, exponent) = scientific(value, target.precision.unwrap_or(6));
+			
+			target.signed(negative, &format!("{}{}", target.point(&integer, &fraction, force_decimal, numbers), exponent_suffix(exponent)))
+		},
+		Trans::SciNot => {
+			// The precision is the total amount of significant digits, instead of the digits after the decimal point.
+			let precision = match target.precision { None => 6, Some(0) => 1, Some(precision) => precision };
+			
+			// Rounding decides which notation is used, such as 999999.5 becoming 1.00000e+06.
+			let exponent = if value == 0.0 { 0 } else { rounded_digits(value, precision).1 };
+			
+			let magnitude = if exponent < -4 || exponent >= precision as isize {
+				let (integer, fraction, exponent) = scientific(value, precision - 1);
+				format!("{}{}", target.point(&integer, &fraction, false, numbers), exponent_suffix(exponent))
+			} else {
+				let (integer, fraction) = fixed(value, (precision as isize - exponent - 1) as usize);
+				target.point(&integer, &fraction, false, numbers)
+			};
+			
+			target.signed(negative, &magnitude)
+		},
+		Trans::Hexfloat { .. } => hexfloat(target, negative, value),
+		_ => unreachable!("float is only called with floating point conversions")
+	}
+}
+
+/// Returns the shortest decimal digits that uniquely identify a positive finite value, along with the exponent of the first digit.
+/// For example, 123.5 becomes [1, 2, 3, 5] and 2.
+fn shortest_digits(value: f64) -> (Vec<u8>, isize) {
+	let digits = |scientific: String| {
+		let (mantissa, exponent) = scientific.split_at(scientific.find('e').expect("LowerExp always writes an exponent"));
+		let exponent = exponent[1..].parse::<isize>().expect("LowerExp writes a valid exponent");
+		
+		(mantissa.bytes().filter(|&b| b != b'.').map(|b| b - b'0').collect::<Vec<u8>>(), exponent)
+	};
+	
+	let shortest = digits(format!("{:e}", value));
+	
+	// Java always uses at least 2 digits, picking the closest ones. This only makes a difference for the smallest subnormal values, 
+	// such as Double.MIN_VALUE being 4.9E-324 instead of 5E-324.
+	if shortest.0.len() == 1 {
+		let two = format!("{:.1e}", value);
+		
+		if two.parse::<f64>() == Ok(value) {
+			return digits(two);
+		}
+	}
+	
+	shortest
 }
 
-fn trans(transform: &Trans, prim: &Prim) -> &'static str {
-	match (transform, prim) {
-		(&Trans::Bool, &Prim::Bool(b)) => if b {"true"} else {"false"},
-		(&Trans::Bool, &Prim::Null) => "false",
-		(&Trans::Bool, &Prim::Undefined) => "false",
-		(&Trans::Bool, _) => "true",
-		_ => panic!()
+/// Keeps the first `keep` digits, rounding half up based on the first removed digit like Java does.
+/// Returns true if rounding carried into a new leading digit, such as 9.99 becoming 10.0, which increases the exponent.
+fn round_half_up(digits: &mut Vec<u8>, keep: usize) -> bool {
+	if digits.len() <= keep {
+		return false;
 	}
-}
\ No newline at end of file
+	
+	let round_up = digits[keep] >= 5;
+	digits.truncate(keep);
+	
+	if !round_up {
+		return false;
+	}
+	
+	for digit in digits.iter_mut().rev() {
+		if *digit == 9 {
+			*digit = 0;
+		} else {
+			*digit += 1;
+			return false;
+		}
+	}
+	
+	digits.insert(0, 1);
+	true
+}
+
+/// Rounds a positive finite value to `significant` digits, returning the digits and the exponent of the first digit.
+fn rounded_digits(value: f64, significant: usize) -> (Vec<u8>, isize) {
+	let (mut digits, mut exponent) = shortest_digits(value);
+	
+	if round_half_up(&mut digits, significant) {
+		exponent += 1;
+	}
+	
+	(digits, exponent)
+}
+
+fn digit_text(digits: &[u8]) -> String {
+	digits.iter().map(|&d| (d + b'0') as char).collect()
+}
+
+/// Writes a non-negative finite value with exactly `precision` fractional digits, returning the integer and fractional digits.
+/// Like Java, this starts from the shortest representation that uniquely identifies the value and then rounds half up,
+/// so 0.125 becomes 0.13 and 1.005 becomes 1.01, unlike the round-half-even of the exact binary value that Rust uses.
+fn fixed(value: f64, precision: usize) -> (String, String) {
+	let (mut digits, exponent) = if value == 0.0 { (vec![0], 0) } else { shortest_digits(value) };
+	let mut point = exponent + 1;
+	
+	if point <= 0 {
+		let zeroes = (1 - point) as usize;
+		digits.splice(0..0, iter::repeat(0).take(zeroes));
+		point = 1;
+	}
+	
+	let mut point = point as usize;
+	
+	if round_half_up(&mut digits, point + precision) {
+		point += 1;
+	}
+	
+	digits.resize(point + precision, 0);
+	
+	(digit_text(&digits[..point]), digit_text(&digits[point..]))
+}
+
+/// Writes a non-negative finite value in scientific notation with exactly `precision` fractional digits, 
+/// returning the single integer digit, the fractional digits, and the exponent.
+fn scientific(value: f64, precision: usize) -> (String, String, isize) {
+	let (mut digits, exponent) = if value == 0.0 { (vec![0], 0) } else { rounded_digits(value, precision + 1) };
+	
+	digits.resize(precision + 1, 0);
+	
+	(digit_text(&digits[..1]), digit_text(&digits[1..]), exponent)
+}
+
+/// Writes the exponent of scientific notation with a sign and at least 2 digits, such as e+05.
+fn exponent_suffix(exponent: isize) -> String {
+	format!("e{}{:02}", if exponent < 0 {'-'} else {'+'}, exponent.abs())
+}
+
+/// Writes a hexadecimal floating point value like Java's %a, such as 0x1.8p1 for 3.0.
+fn hexfloat(target: &Target, negative: bool, value: f64) -> String {
+	// Without a precision every digit is written, and a precision of 0 is treated as 1.
+	let precision = match target.precision { None => 0, Some(0) => 1, Some(precision) => precision };
+	let digits = hex_double(value, precision);
+	let exponent = digits.find('p').expect("hex_double always writes an exponent");
+	
+	let mut out = target.sign_prefix(negative).to_owned();
+	out.push_str("0x");
+	
+	// Java ignores the zeroes added for the precision when padding, so the result can be wider than requested.
+	if target.strategy == PaddingStrategy::ZeroPad {
+		out.extend(iter::repeat('0').take(target.min_width.saturating_sub(out.len() + digits.len())));
+	}
+	
+	out.push_str(&digits[..exponent]);
+	
+	if precision != 0 {
+		let written = digits[..exponent].find('.').map(|point| exponent - point - 1).unwrap_or(0);
+		out.extend(iter::repeat('0').take(precision.saturating_sub(written)));
+	}
+	
+	out.push_str(&digits[exponent..]);
+	out.push_str(target.sign_suffix(negative));
+	
+	out
+}
+
+/// Writes a non-negative finite value like Java's `Double.toHexString`, without the 0x prefix.
+fn java_hex_string(value: f64) -> String {
+	let bits = value.to_bits();
+	let exponent = (bits >> 52) as i64;
+	let significand = format!("{:013x}", bits & 0xF_FFFF_FFFF_FFFF);
+	let significand = match significand.trim_end_matches('0') { "" => "0", trimmed => trimmed };
+	
+	if value == 0.0 {
+		"0.0p0".to_owned()
+	} else if exponent == 0 {
+		format!("0.{}p-1022", significand)
+	} else {
+		format!("1.{}p{}", significand, exponent - 1023)
+	}
+}
+
+/// Rounds a non-negative finite value to `precision` hexadecimal digits after the point, following Java's `Formatter.hexDouble`.
+/// Subnormal values are normalized, so they start with 1 instead of 0 when rounded.
+fn hex_double(value: f64, precision: usize) -> String {
+	if value == 0.0 || precision == 0 || precision >= 13 {
+		return java_hex_string(value);
+	}
+	
+	let subnormal = value.to_bits() >> 52 == 0;
+	let value = if subnormal { value * (1u64 << 54) as f64 } else { value };
+	
+	let shift = 52 - precision as u32 * 4;
+	let bits = value.to_bits();
+	
+	let mut significand = bits >> shift;
+	let discarded = bits & !(!0u64 << shift);
+	
+	let half = 1u64 << (shift - 1);
+	
+	// Round half to even.
+	if discarded & half != 0 && (significand & 1 != 0 || discarded & !half != 0) {
+		significand += 1;
+	}
+	
+	let rounded = f64::from_bits(significand << shift);
+	
+	if rounded.is_infinite() {
+		return "1.0p1024".to_owned();
+	}
+	
+	let digits = java_hex_string(rounded);
+	
+	if !subnormal {
+		return digits;
+	}
+	
+	let exponent = digits.find('p').expect("java_hex_string always writes an exponent");
+	let scaled = digits[exponent + 1..].parse::<i64>().expect("java_hex_string writes a valid exponent");
+	
+	format!("{}p{}", &digits[..exponent], scaled - 54)
+}
+
+/// Writes a double the way Java's `Double.toString` does: plain notation between 10^-3 and 10^7, and computerized scientific notation otherwise.
+pub fn java_double(value: f64) -> String {
+	if value.is_nan() {
+		return "NaN".to_owned();
+	} else if value.is_infinite() {
+		return if value < 0.0 {"-Infinity"} else {"Infinity"}.to_owned();
+	}
+	
+	let magnitude = value.abs();
+	
+	if magnitude == 0.0 || (magnitude >= 1e-3 && magnitude < 1e7) {
+		// Debug always writes at least one fractional digit, such as "100.0".
+		format!("{:?}", value)
+	} else {
+		let (digits, exponent) = shortest_digits(magnitude);
+		let fraction = if digits.len() > 1 { digit_text(&digits[1..]) } else { "0".to_owned() };
+		
+		format!("{}{}.{}E{}", if value < 0.0 {"-"} else {""}, digits[0], fraction, exponent)
+	}
+}
+
+#[test]
+fn test_transform() {
+	use text::formatter::FormatCommand;
+	
+	let format = |code: &str, prim: Prim| {
+		let (_, cmd) = FormatCommand::parse(code).unwrap();
+		Target::from(&cmd).unwrap().format(&prim, cmd.upper)
+	};
+	
+	assert_eq!(format("%5.2f", Prim::Float(3.14159)).unwrap(), " 3.14");
+	assert_eq!(format("%,d", Prim::Integer(1234567)).unwrap(), "1,234,567");
+	assert_eq!(format("%,.2f", Prim::Float(-1234.5)).unwrap(), "-1,234.50");
+	assert_eq!(format("%08.3f", Prim::Float(-2.0005)).unwrap(), "-002.001");
+	assert_eq!(format("%.1f", Prim::Float(0.15)).unwrap(), "0.2");
+	assert_eq!(format("%.0f", Prim::Float(99.5)).unwrap(), "100");
+	assert_eq!(format("%#.0f", Prim::Float(1.0)).unwrap(), "1.");
+	assert_eq!(format("%f", Prim::Integer(2)).unwrap(), "2.000000");
+	assert_eq!(format("%(,08d", Prim::Integer(-1234)).unwrap(), "(01,234)");
+	assert_eq!(format("%+d", Prim::Integer(5)).unwrap(), "+5");
+	assert_eq!(format("% d", Prim::Integer(5)).unwrap(), " 5");
+	assert_eq!(format("%d", Prim::Integer(::std::i64::MIN)).unwrap(), "-9223372036854775808");
+	assert_eq!(format("%-6d|", Prim::Integer(-12)).unwrap(), "-12   ");
+	assert_eq!(format("%#06x", Prim::Integer(255)).unwrap(), "0x00ff");
+	assert_eq!(format("%X", Prim::Integer(-1)).unwrap(), "FFFFFFFFFFFFFFFF");
+	assert_eq!(format("%#o", Prim::Integer(8)).unwrap(), "010");
+	assert_eq!(format("%+f", Prim::Float(::std::f64::INFINITY)).unwrap(), "+Infinity");
+	assert_eq!(format("%08f", Prim::Float(::std::f64::NAN)).unwrap(), "     NaN");
+	assert_eq!(format("%-7.3S|", Prim::String("abcdef".to_owned())).unwrap(), "ABC    ");
+	assert_eq!(format("%5b", Prim::Null).unwrap(), "false");
+	assert_eq!(format("%.2b", Prim::Integer(0)).unwrap(), "tr");
+	assert_eq!(format("%s", Prim::Float(1e21)).unwrap(), "1.0E21");
+	assert_eq!(format("%s", Prim::Float(100.0)).unwrap(), "100.0");
+	assert_eq!(format("%d", Prim::String("three".to_owned())), None);
+	assert_eq!(format("%d", Prim::Float(2.5)), None);
+}
+
+#[test]
+fn test_hash_and_char() {
+	use text::formatter::FormatCommand;
+	use serde_json;
+	
+	let format = |code: &str, prim: Prim| {
+		let (_, cmd) = FormatCommand::parse(code).unwrap();
+		Target::from(&cmd).unwrap().format(&prim, cmd.upper)
+	};
+	
+	let string = |s: &str| Prim::String(s.to_owned());
+	
+	// Generated with String.format(Locale.US, code, value) on Java 17, with Integer, Long, Double and String arguments.
+	assert_eq!(format("%h", string("hello")).unwrap(), "5e918d2");
+	assert_eq!(format("%H", string("hello")).unwrap(), "5E918D2");
+	assert_eq!(format("%.3h", string("hello")).unwrap(), "5e9");
+	assert_eq!(format("%h", string("")).unwrap(), "0");
+	assert_eq!(format("%h", string("\u{E9}\u{1F600}")).unwrap(), "1e780c");
+	assert_eq!(format("%h", Prim::Integer(42)).unwrap(), "2a");
+	assert_eq!(format("%h", Prim::Integer(4294967296)).unwrap(), "1");
+	assert_eq!(format("%h", Prim::Integer(::std::i64::MIN)).unwrap(), "80000000");
+	assert_eq!(format("%h", Prim::Float(1.5)).unwrap(), "3ff80000");
+	assert_eq!(format("%h", Prim::Float(-0.0)).unwrap(), "80000000");
+	assert_eq!(format("%h", Prim::Float(::std::f64::NAN)).unwrap(), "7ff80000");
+	assert_eq!(format("%h", Prim::Bool(true)).unwrap(), "4cf");
+	assert_eq!(format("%h", Prim::Bool(false)).unwrap(), "4d5");
+	assert_eq!(format("%h", Prim::Null).unwrap(), "null");
+	assert_eq!(format("%8h", string("a")).unwrap(), "      61");
+	assert_eq!(format("%-8H", Prim::Integer(255)).unwrap(), "FF      ");
+	assert_eq!(format("%B", Prim::Bool(false)).unwrap(), "FALSE");
+	
+	assert_eq!(format("%c", Prim::Integer(65)).unwrap(), "A");
+	assert_eq!(format("%C", Prim::Integer(97)).unwrap(), "A");
+	assert_eq!(format("%c", Prim::Integer(0x1F600)).unwrap(), "\u{1F600}");
+	assert_eq!(format("%-3c", Prim::Integer(66)).unwrap(), "B  ");
+	assert_eq!(format("%c", string("x")).unwrap(), "x");
+	assert_eq!(format("%c", Prim::Null).unwrap(), "null");
+	assert_eq!(format("%c", Prim::Integer(0x110000)), None);
+	assert_eq!(format("%c", Prim::Integer(-1)), None);
+	assert_eq!(format("%c", Prim::Integer(0xD800)), None);
+	assert_eq!(format("%c", string("xy")), None);
+	
+	// Numbers given as text are parsed, but other text is rejected.
+	assert_eq!(format("%x", string("255")).unwrap(), "ff");
+	assert_eq!(format("%x", string("ff")), None);
+	
+	let json = |text: &str| Prim::from_json(&serde_json::from_str(text).unwrap());
+	
+	assert_eq!(json("12"), Some(Prim::Integer(12)));
+	assert_eq!(json("1.5"), Some(Prim::Float(1.5)));
+	assert_eq!(json("\"12\""), Some(string("12")));
+	assert_eq!(json("null"), Some(Prim::Null));
+	assert_eq!(json("{\"text\": \"a\"}"), None);
+}
+
+#[test]
+fn test_java_parity() {
+	use text::formatter::FormatCommand;
+	
+	// Generated with String.format(Locale.US, code, value) on Java 17.
+	let table = [
+		("%e", Prim::Float(0.0), "0.000000e+00"),
+		("%e", Prim::Float(-0.0), "-0.000000e+00"),
+		("%e", Prim::Float(0.125), "1.250000e-01"),
+		("%e", Prim::Float(1.005), "1.005000e+00"),
+		("%E", Prim::Float(9.9999996), "1.000000E+01"),
+		("%E", Prim::Float(999999.5), "9.999995E+05"),
+		("%E", Prim::Float(-1234.5678), "-1.234568E+03"),
+		("%E", Prim::Float(0.0001), "1.000000E-04"),
+		("%.0e", Prim::Float(0.00009999996), "1e-04"),
+		("%.0e", Prim::Float(6.02214076E23), "6e+23"),
+		("%.0e", Prim::Float(-1.0E-300), "-1e-300"),
+		("%.0e", Prim::Float(4.9E-324), "5e-324"),
+		("%#.0e", Prim::Float(1.7976931348623157E308), "2.e+308"),
+		("%#.0e", Prim::Float(::std::f64::NEG_INFINITY), "-Infinity"),
+		("%#.0e", Prim::Float(::std::f64::NAN), "NaN"),
+		("%#.0e", Prim::Float(123456.789), "1.e+05"),
+		("%.3e", Prim::Float(0.1), "1.000e-01"),
+		("%.3e", Prim::Float(12345.0), "1.235e+04"),
+		("%.3e", Prim::Float(0.0), "0.000e+00"),
+		("%.3e", Prim::Float(-0.0), "-0.000e+00"),
+		("%12.4e", Prim::Float(0.125), "  1.2500e-01"),
+		("%12.4e", Prim::Float(1.005), "  1.0050e+00"),
+		("%12.4e", Prim::Float(9.9999996), "  1.0000e+01"),
+		("%12.4e", Prim::Float(999999.5), "  1.0000e+06"),
+		("%-12.2e", Prim::Float(-1234.5678), "-1.23e+03   "),
+		("%-12.2e", Prim::Float(0.0001), "1.00e-04    "),
+		("%-12.2e", Prim::Float(0.00009999996), "1.00e-04    "),
+		("%-12.2e", Prim::Float(6.02214076E23), "6.02e+23    "),
+		("%012.3e", Prim::Float(-1.0E-300), "-01.000e-300"),
+		("%012.3e", Prim::Float(4.9E-324), "004.900e-324"),
+		("%012.3e", Prim::Float(1.7976931348623157E308), "001.798e+308"),
+		("%012.3e", Prim::Float(::std::f64::NEG_INFINITY), "   -Infinity"),
+		("%+e", Prim::Float(::std::f64::NAN), "NaN"),
+		("%+e", Prim::Float(123456.789), "+1.234568e+05"),
+		("%+e", Prim::Float(0.1), "+1.000000e-01"),
+		("%+e", Prim::Float(12345.0), "+1.234500e+04"),
+		("% e", Prim::Float(0.0), " 0.000000e+00"),
+		("% e", Prim::Float(-0.0), "-0.000000e+00"),
+		("% e", Prim::Float(0.125), " 1.250000e-01"),
+		("% e", Prim::Float(1.005), " 1.005000e+00"),
+		("%(e", Prim::Float(9.9999996), "1.000000e+01"),
+		("%(e", Prim::Float(999999.5), "9.999995e+05"),
+		("%(e", Prim::Float(-1234.5678), "(1.234568e+03)"),
+		("%(e", Prim::Float(0.0001), "1.000000e-04"),
+		("%(012.2e", Prim::Float(0.00009999996), "00001.00e-04"),
+		("%(012.2e", Prim::Float(6.02214076E23), "00006.02e+23"),
+		("%(012.2e", Prim::Float(-1.0E-300), "(01.00e-300)"),
+		("%(012.2e", Prim::Float(4.9E-324), "0004.90e-324"),
+		("%g", Prim::Float(1.7976931348623157E308), "1.79769e+308"),
+		("%g", Prim::Float(::std::f64::NEG_INFINITY), "-Infinity"),
+		("%g", Prim::Float(::std::f64::NAN), "NaN"),
+		("%g", Prim::Float(123456.789), "123457"),
+		("%G", Prim::Float(0.1), "0.100000"),
+		("%G", Prim::Float(12345.0), "12345.0"),
+		("%G", Prim::Float(0.0), "0.00000"),
+		("%G", Prim::Float(-0.0), "-0.00000"),
+		("%.0g", Prim::Float(0.125), "0.1"),
+		("%.0g", Prim::Float(1.005), "1"),
+		("%.0g", Prim::Float(9.9999996), "1e+01"),
+		("%.0g", Prim::Float(999999.5), "1e+06"),
+		("%.1g", Prim::Float(-1234.5678), "-1e+03"),
+		("%.1g", Prim::Float(0.0001), "0.0001"),
+		("%.1g", Prim::Float(0.00009999996), "0.0001"),
+		("%.1g", Prim::Float(6.02214076E23), "6e+23"),
+		("%.3g", Prim::Float(-1.0E-300), "-1.00e-300"),
+		("%.3g", Prim::Float(4.9E-324), "4.90e-324"),
+		("%.3g", Prim::Float(1.7976931348623157E308), "1.80e+308"),
+		("%.3g", Prim::Float(::std::f64::NEG_INFINITY), "-Infinity"),
+		("%10.3g", Prim::Float(::std::f64::NAN), "       NaN"),
+		("%10.3g", Prim::Float(123456.789), "  1.23e+05"),
+		("%10.3g", Prim::Float(0.1), "     0.100"),
+		("%10.3g", Prim::Float(12345.0), "  1.23e+04"),
+		("%-10.4g", Prim::Float(0.0), "0.000     "),
+		("%-10.4g", Prim::Float(-0.0), "-0.000    "),
+		("%-10.4g", Prim::Float(0.125), "0.1250    "),
+		("%-10.4g", Prim::Float(1.005), "1.005     "),
+		("%010.4g", Prim::Float(9.9999996), "0000010.00"),
+		("%010.4g", Prim::Float(999999.5), "01.000e+06"),
+		("%010.4g", Prim::Float(-1234.5678), "-000001235"),
+		("%010.4g", Prim::Float(0.0001), "00.0001000"),
+		("%,g", Prim::Float(0.00009999996), "0.000100000"),
+		("%,g", Prim::Float(6.02214076E23), "6.02214e+23"),
+		("%,g", Prim::Float(-1.0E-300), "-1.00000e-300"),
+		("%,g", Prim::Float(4.9E-324), "4.90000e-324"),
+		("%,.8g", Prim::Float(1.7976931348623157E308), "1.7976931e+308"),
+		("%,.8g", Prim::Float(::std::f64::NEG_INFINITY), "-Infinity"),
+		("%,.8g", Prim::Float(::std::f64::NAN), "NaN"),
+		("%,.8g", Prim::Float(123456.789), "123,456.79"),
+		("%+g", Prim::Float(0.1), "+0.100000"),
+		("%+g", Prim::Float(12345.0), "+12345.0"),
+		("%+g", Prim::Float(0.0), "+0.00000"),
+		("%+g", Prim::Float(-0.0), "-0.00000"),
+		("%(g", Prim::Float(0.125), "0.125000"),
+		("%(g", Prim::Float(1.005), "1.00500"),
+		("%(g", Prim::Float(9.9999996), "10.0000"),
+		("%(g", Prim::Float(999999.5), "1.00000e+06"),
+		("%(,012.3g", Prim::Float(-1234.5678), "(001.23e+03)"),
+		("%(,012.3g", Prim::Float(0.0001), "00000.000100"),
+		("%(,012.3g", Prim::Float(0.00009999996), "00000.000100"),
+		("%(,012.3g", Prim::Float(6.02214076E23), "00006.02e+23"),
+		("%a", Prim::Float(-1.0E-300), "-0x1.56e1fc2f8f359p-997"),
+		("%a", Prim::Float(4.9E-324), "0x0.0000000000001p-1022"),
+		("%a", Prim::Float(1.7976931348623157E308), "0x1.fffffffffffffp1023"),
+		("%a", Prim::Float(::std::f64::NEG_INFINITY), "-Infinity"),
+		("%A", Prim::Float(::std::f64::NAN), "NAN"),
+		("%A", Prim::Float(123456.789), "0X1.E240C9FBE76C9P16"),
+		("%A", Prim::Float(0.1), "0X1.999999999999AP-4"),
+		("%A", Prim::Float(12345.0), "0X1.81C8P13"),
+		("%.0a", Prim::Float(0.0), "0x0.0p0"),
+		("%.0a", Prim::Float(-0.0), "-0x0.0p0"),
+		("%.0a", Prim::Float(0.125), "0x1.0p-3"),
+		("%.0a", Prim::Float(1.005), "0x1.0p0"),
+		("%.1a", Prim::Float(9.9999996), "0x1.4p3"),
+		("%.1a", Prim::Float(999999.5), "0x1.fp19"),
+		("%.1a", Prim::Float(-1234.5678), "-0x1.3p10"),
+		("%.1a", Prim::Float(0.0001), "0x1.ap-14"),
+		("%.3a", Prim::Float(0.00009999996), "0x1.a37p-14"),
+		("%.3a", Prim::Float(6.02214076E23), "0x1.fe2p78"),
+		("%.3a", Prim::Float(-1.0E-300), "-0x1.56ep-997"),
+		("%.3a", Prim::Float(4.9E-324), "0x1.000p-1074"),
+		("%.13a", Prim::Float(1.7976931348623157E308), "0x1.fffffffffffffp1023"),
+		("%.13a", Prim::Float(::std::f64::NEG_INFINITY), "-Infinity"),
+		("%.13a", Prim::Float(::std::f64::NAN), "NaN"),
+		("%.13a", Prim::Float(123456.789), "0x1.e240c9fbe76c9p16"),
+		("%.15a", Prim::Float(0.1), "0x1.999999999999a00p-4"),
+		("%.15a", Prim::Float(12345.0), "0x1.81c800000000000p13"),
+		("%.15a", Prim::Float(0.0), "0x0.000000000000000p0"),
+		("%.15a", Prim::Float(-0.0), "-0x0.000000000000000p0"),
+		("%12a", Prim::Float(0.125), "    0x1.0p-3"),
+		("%12a", Prim::Float(1.005), "0x1.0147ae147ae14p0"),
+		("%12a", Prim::Float(9.9999996), "0x1.3fffff29406b3p3"),
+		("%12a", Prim::Float(999999.5), "0x1.e847fp19"),
+		("%-14.2a", Prim::Float(-1234.5678), "-0x1.35p10    "),
+		("%-14.2a", Prim::Float(0.0001), "0x1.a3p-14    "),
+		("%-14.2a", Prim::Float(0.00009999996), "0x1.a3p-14    "),
+		("%-14.2a", Prim::Float(6.02214076E23), "0x1.fep78     "),
+		("%012a", Prim::Float(-1.0E-300), "-0x1.56e1fc2f8f359p-997"),
+		("%012a", Prim::Float(4.9E-324), "0x0.0000000000001p-1022"),
+		("%012a", Prim::Float(1.7976931348623157E308), "0x1.fffffffffffffp1023"),
+		("%012a", Prim::Float(::std::f64::NEG_INFINITY), "   -Infinity"),
+		("%012.2a", Prim::Float(::std::f64::NAN), "         NaN"),
+		("%012.2a", Prim::Float(123456.789), "0x0001.e2p16"),
+		("%012.2a", Prim::Float(0.1), "0x0001.9ap-4"),
+		("%012.2a", Prim::Float(12345.0), "0x0001.82p13"),
+		("%+a", Prim::Float(0.0), "+0x0.0p0"),
+		("%+a", Prim::Float(-0.0), "-0x0.0p0"),
+		("%+a", Prim::Float(0.125), "+0x1.0p-3"),
+		("%+a", Prim::Float(1.005), "+0x1.0147ae147ae14p0"),
+		("% a", Prim::Float(9.9999996), " 0x1.3fffff29406b3p3"),
+		("% a", Prim::Float(999999.5), " 0x1.e847fp19"),
+		("% a", Prim::Float(-1234.5678), "-0x1.34a456d5cfaadp10"),
+		("% a", Prim::Float(0.0001), " 0x1.a36e2eb1c432dp-14"),
+		("%f", Prim::Float(0.00009999996), "0.000100"),
+		("%f", Prim::Float(6.02214076E23), "602214076000000000000000.000000"),
+		("%f", Prim::Float(-1.0E-300), "-0.000000"),
+		("%f", Prim::Float(4.9E-324), "0.000000"),
+		("%.0f", Prim::Float(1.7976931348623157E308), "179769313486231570000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000"),
+		("%.0f", Prim::Float(::std::f64::NEG_INFINITY), "-Infinity"),
+		("%.0f", Prim::Float(::std::f64::NAN), "NaN"),
+		("%.0f", Prim::Float(123456.789), "123457"),
+		("%.2f", Prim::Float(0.1), "0.10"),
+		("%.2f", Prim::Float(12345.0), "12345.00"),
+		("%.2f", Prim::Float(0.0), "0.00"),
+		("%.2f", Prim::Float(-0.0), "-0.00"),
+		("%#.0f", Prim::Float(0.125), "0."),
+		("%#.0f", Prim::Float(1.005), "1."),
+		("%#.0f", Prim::Float(9.9999996), "10."),
+		("%#.0f", Prim::Float(999999.5), "1000000."),
+		("%,.3f", Prim::Float(-1234.5678), "-1,234.568"),
+		("%,.3f", Prim::Float(0.0001), "0.000"),
+		("%,.3f", Prim::Float(0.00009999996), "0.000"),
+		("%,.3f", Prim::Float(6.02214076E23), "602,214,076,000,000,000,000,000.000"),
+		("%(,.2f", Prim::Float(-1.0E-300), "(0.00)"),
+		("%(,.2f", Prim::Float(4.9E-324), "0.00"),
+		("%(,.2f", Prim::Float(1.7976931348623157E308), "179,769,313,486,231,570,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000,000.00"),
+		("%(,.2f", Prim::Float(::std::f64::NEG_INFINITY), "(Infinity)"),
+		("%+010.2f", Prim::Float(::std::f64::NAN), "       NaN"),
+		("%+010.2f", Prim::Float(123456.789), "+123456.79"),
+		("%+010.2f", Prim::Float(0.1), "+000000.10"),
+		("%+010.2f", Prim::Float(12345.0), "+012345.00"),
+		("%-12.1f", Prim::Float(0.0), "0.0         "),
+		("%-12.1f", Prim::Float(-0.0), "-0.0        "),
+		("%-12.1f", Prim::Float(0.125), "0.1         "),
+		("%-12.1f", Prim::Float(1.005), "1.0         "),
+		("%d", Prim::Integer(0), "0"),
+		("%d", Prim::Integer(-7), "-7"),
+		("%d", Prim::Integer(1234567), "1234567"),
+		("%5d", Prim::Integer(-1234567), "-1234567"),
+		("%5d", Prim::Integer(::std::i64::MAX), "9223372036854775807"),
+		("%5d", Prim::Integer(::std::i64::MIN), "-9223372036854775808"),
+		("%-5d", Prim::Integer(42), "42   "),
+		("%-5d", Prim::Integer(0), "0    "),
+		("%-5d", Prim::Integer(-7), "-7   "),
+		("%05d", Prim::Integer(1234567), "1234567"),
+		("%05d", Prim::Integer(-1234567), "-1234567"),
+		("%05d", Prim::Integer(::std::i64::MAX), "9223372036854775807"),
+		("%+d", Prim::Integer(::std::i64::MIN), "-9223372036854775808"),
+		("%+d", Prim::Integer(42), "+42"),
+		("%+d", Prim::Integer(0), "+0"),
+		("% d", Prim::Integer(-7), "-7"),
+		("% d", Prim::Integer(1234567), " 1234567"),
+		("% d", Prim::Integer(-1234567), "-1234567"),
+		("%(d", Prim::Integer(::std::i64::MAX), "9223372036854775807"),
+		("%(d", Prim::Integer(::std::i64::MIN), "(9223372036854775808)"),
+		("%(d", Prim::Integer(42), "42"),
+		("%,d", Prim::Integer(0), "0"),
+		("%,d", Prim::Integer(-7), "-7"),
+		("%,d", Prim::Integer(1234567), "1,234,567"),
+		("%(,010d", Prim::Integer(-1234567), "(1,234,567)"),
+		("%(,010d", Prim::Integer(::std::i64::MAX), "9,223,372,036,854,775,807"),
+		("%(,010d", Prim::Integer(::std::i64::MIN), "(9,223,372,036,854,775,808)"),
+		("%+,d", Prim::Integer(42), "+42"),
+		("%+,d", Prim::Integer(0), "+0"),
+		("%+,d", Prim::Integer(-7), "-7"),
+		("%o", Prim::Integer(1234567), "4553207"),
+		("%o", Prim::Integer(-1234567), "1777777777777773224571"),
+		("%o", Prim::Integer(::std::i64::MAX), "777777777777777777777"),
+		("%#o", Prim::Integer(::std::i64::MIN), "01000000000000000000000"),
+		("%#o", Prim::Integer(42), "052"),
+		("%#o", Prim::Integer(0), "00"),
+		("%08o", Prim::Integer(-7), "1777777777777777777771"),
+		("%08o", Prim::Integer(1234567), "04553207"),
+		("%08o", Prim::Integer(-1234567), "1777777777777773224571"),
+		("%x", Prim::Integer(::std::i64::MAX), "7fffffffffffffff"),
+		("%x", Prim::Integer(::std::i64::MIN), "8000000000000000"),
+		("%x", Prim::Integer(42), "2a"),
+		("%#x", Prim::Integer(0), "0x0"),
+		("%#x", Prim::Integer(-7), "0xfffffffffffffff9"),
+		("%#x", Prim::Integer(1234567), "0x12d687"),
+		("%#010X", Prim::Integer(-1234567), "0XFFFFFFFFFFED2979"),
+		("%#010X", Prim::Integer(::std::i64::MAX), "0X7FFFFFFFFFFFFFFF"),
+		("%#010X", Prim::Integer(::std::i64::MIN), "0X8000000000000000"),
+		("%-8x", Prim::Integer(42), "2a      "),
+		("%-8x", Prim::Integer(0), "0       "),
+		("%-8x", Prim::Integer(-7), "fffffffffffffff9")
+	];
+	
+	for &(code, ref prim, expected) in table.iter() {
+		let (_, cmd) = FormatCommand::parse(code).unwrap();
+		
+		assert_eq!(Target::from(&cmd).unwrap().format(prim, cmd.upper).unwrap(), expected, "formatting {:?} with {}", prim, code);
+	}
+}
+
+#[test]
+fn test_number_formats() {
+	use text::formatter::FormatCommand;
+	
+	// Generated with String.format(new Locale(language, country), code, value) on Java 17, using the locale data of Java 8.
+	let table = [
+		("de_DE", "%,d", Prim::Integer(-1234567), "-1.234.567"),
+		("de_DE", "%,.2f", Prim::Float(1234567.891), "1.234.567,89"),
+		("de_DE", "%,012.1f", Prim::Float(-9876.54), "-00009.876,5"),
+		("de_DE", "%.3e", Prim::Float(1234.5), "1,235e+03"),
+		("de_DE", "%,g", Prim::Float(123456.7), "123.457"),
+		("de_DE", "%(,d", Prim::Integer(-1000), "(1.000)"),
+		("de_DE", "%f", Prim::Float(0.5), "0,500000"),
+		("de_DE", "%#.0f", Prim::Float(3.0), "3,"),
+		("fr_FR", "%,d", Prim::Integer(-1234567), "-1\u{A0}234\u{A0}567"),
+		("fr_FR", "%,.2f", Prim::Float(1234567.891), "1\u{A0}234\u{A0}567,89"),
+		("fr_FR", "%,012.1f", Prim::Float(-9876.54), "-00009\u{A0}876,5"),
+		("fr_FR", "%.3e", Prim::Float(1234.5), "1,235e+03"),
+		("fr_FR", "%,g", Prim::Float(123456.7), "123\u{A0}457"),
+		("fr_FR", "%(,d", Prim::Integer(-1000), "(1\u{A0}000)"),
+		("fr_FR", "%f", Prim::Float(0.5), "0,500000"),
+		("fr_FR", "%#.0f", Prim::Float(3.0), "3,"),
+		("de_CH", "%,d", Prim::Integer(-1234567), "-1'234'567"),
+		("de_CH", "%,.2f", Prim::Float(1234567.891), "1'234'567.89"),
+		("de_CH", "%,012.1f", Prim::Float(-9876.54), "-00009'876.5"),
+		("de_CH", "%.3e", Prim::Float(1234.5), "1.235e+03"),
+		("de_CH", "%,g", Prim::Float(123456.7), "123'457"),
+		("de_CH", "%(,d", Prim::Integer(-1000), "(1'000)"),
+		("de_CH", "%f", Prim::Float(0.5), "0.500000"),
+		("de_CH", "%#.0f", Prim::Float(3.0), "3.")
+	];
+	
+	for &(locale, code, ref prim, expected) in table.iter() {
+		let (_, cmd) = FormatCommand::parse(code).unwrap();
+		let numbers = NumberFormat::for_locale(locale);
+		
+		assert_eq!(Target::from(&cmd).unwrap().format_in(prim, cmd.upper, &numbers, &Zone::utc()).unwrap(), expected, "formatting {:?} with {} in {}", prim, code, locale);
+	}
+	
+	assert_eq!(NumberFormat::for_locale("de_de"), NumberFormat::for_locale("de_DE"));
+	assert_eq!(NumberFormat::for_locale("en_GB"), NumberFormat::en_us());
+	assert_eq!(NumberFormat::for_locale("xx_XX"), NumberFormat::en_us());
+}
+
+#[test]
+fn test_validation() {
+	use text::formatter::{FormatCommand, TimeKind};
+	
+	let check = |code: &str| {
+		let (_, cmd) = FormatCommand::parse(code).unwrap();
+		Target::from(&cmd).map(|_| ())
+	};
+	
+	// Java throws FormatFlagsConversionMismatchException, IllegalFormatPrecisionException or MissingFormatWidthException for these.
+	assert_eq!(check("%+s"), Err(Error::FlagMismatch(Flag::Plus, Kind::String)));
+	assert_eq!(check("%0s"), Err(Error::FlagMismatch(Flag::ZeroPad, Kind::String)));
+	assert_eq!(check("%(b"), Err(Error::FlagMismatch(Flag::Parentheses, Kind::Bool)));
+	assert_eq!(check("%+c"), Err(Error::FlagMismatch(Flag::Plus, Kind::Unicode)));
+	assert_eq!(check("%,x"), Err(Error::FlagMismatch(Flag::Group, Kind::Hex)));
+	assert_eq!(check("% x"), Err(Error::FlagMismatch(Flag::LeadingSpace, Kind::Hex)));
+	assert_eq!(check("%(o"), Err(Error::FlagMismatch(Flag::Parentheses, Kind::Octal)));
+	assert_eq!(check("%,e"), Err(Error::FlagMismatch(Flag::Group, Kind::CompSciNot)));
+	assert_eq!(check("%(a"), Err(Error::FlagMismatch(Flag::Parentheses, Kind::Hexfloat)));
+	assert_eq!(check("%0tH"), Err(Error::FlagMismatch(Flag::ZeroPad, Kind::Time(TimeKind::Hour24))));
+	assert_eq!(check("%#d"), Err(Error::NoAlternate));
+	assert_eq!(check("%.2c"), Err(Error::Precision(Kind::Unicode)));
+	assert_eq!(check("%.2d"), Err(Error::Precision(Kind::Decimal)));
+	assert_eq!(check("%.2tH"), Err(Error::Precision(Kind::Time(TimeKind::Hour24))));
+	assert_eq!(check("%-s"), Err(Error::MissingWidth(Flag::LeftJustify)));
+	assert_eq!(check("%0d"), Err(Error::MissingWidth(Flag::ZeroPad)));
+	assert_eq!(check("%-tH"), Err(Error::MissingWidth(Flag::LeftJustify)));
+	
+	for code in &["%-5s", "%.3b", "%.3h", "%08x", "%(e", "%,g", "%+a", "%-4tH", "%(,.2f", "% d"] {
+		assert_eq!(check(code), Ok(()), "{} should be accepted", code);
+	}
+}
@@ -0,0 +1,311 @@
+//! A binary cache of compiled language files, so that they do not need to be read and compiled again on every launch.
+//! The cache starts with a hash of the language file it was created from and its format, and is only used if both are unchanged.
+//! Reading the cache copies every translation into an owned `Directory`, so the file is read normally instead of being memory mapped.
+
+use text::language::{Directory, Compiled, SimpleFormatCmd, Error, Format, strip_bom};
+use text::formatter::{FormatCommand, Index, Flags, Kind};
+use text::formatter::transform::Target;
+use resource::Asset;
+use std::fs::{self, File};
+use std::path::Path;
+use std::process;
+use std::str;
+use std::io::{self, Read, Write};
+use std::fmt::{self, Formatter, Display};
+
+const MAGIC: &'static [u8; 4] = b"QLLC";
+/// Increased whenever the layout of the cache changes, which makes older caches stale.
+const VERSION: u32 = 3;
+/// Stored in place of a width or precision that was not given.
+const NONE: u32 = u32::max_value();
+
+#[derive(Debug)]
+pub enum CacheError {
+	Io(io::Error),
+	/// The cache was created from a different language file, a file in a different format, or by a different version.
+	Stale,
+	/// The cache is truncated or contains invalid data.
+	Corrupt
+}
+
+impl Display for CacheError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			CacheError::Io(ref err) => write!(f, "could not read the language cache: {}", err),
+			CacheError::Stale => write!(f, "the language cache is out of date"),
+			CacheError::Corrupt => write!(f, "the language cache is corrupt")
+		}
+	}
+}
+
+/// Hashes the contents of a language file with 64 bit FNV-1a. Unlike the hasher of the standard library, the result never changes between versions of Rust.
+pub fn hash(source: &[u8]) -> u64 {
+	source.iter().fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Returns the format that `Directory::load` reads the language file `source` named `name` as.
+/// The same bytes are loaded differently depending on the extension of the name, so the format is stored next to the hash.
+pub fn format(source: &[u8], name: &str) -> Format {
+	Format::detect(name, strip_bom(&String::from_utf8_lossy(source)))
+}
+
+fn format_tag(format: Format) -> u8 {
+	match format {
+		Format::Lang => 0,
+		Format::Json => 1
+	}
+}
+
+/// Writes the cache of a language that was loaded from the language file `source` in the format `format`.
+/// Translations that failed to load are left out, which makes no difference to `lookup` as it skips them either way.
+/// However, a directory read from the cache does not contain them, so `consistency::compare` reports them as missing instead of invalid.
+pub fn save<W>(language: &Directory, source: &[u8], format: Format, write: &mut W) -> io::Result<()> where W: Write {
+	let entries = language.entries().into_iter().filter_map(|(key, value)| value.as_ref().ok().map(|compiled| (key, compiled))).collect::<Vec<_>>();
+	
+	let mut out = Vec::new();
+	
+	out.extend_from_slice(MAGIC);
+	push_u32(&mut out, VERSION);
+	out.push(format_tag(format));
+	push_u64(&mut out, hash(source));
+	push_u32(&mut out, entries.len() as u32);
+	
+	for (key, compiled) in entries {
+		push_str(&mut out, &key);
+		push_str(&mut out, &compiled.string);
+		push_u32(&mut out, compiled.commands.len() as u32);
+		
+		for cmd in &compiled.commands {
+			push_u32(&mut out, cmd.string_start as u32);
+			push_u32(&mut out, cmd.arg_index as u32);
+			push_command(&mut out, &cmd.command);
+		}
+	}
+	
+	write.write_all(&out)
+}
+
+/// Reads a cache, checking that it was created from the language file `source` in the format `format`.
+pub fn read(bytes: &[u8], source: &[u8], format: Format) -> Result<Directory, CacheError> {
+	let mut reader = Reader { bytes: bytes, position: 0 };
+	
+	if reader.bytes(MAGIC.len())? != MAGIC {
+		return Err(CacheError::Corrupt);
+	}
+	
+	if reader.u32()? != VERSION || reader.u8()? != format_tag(format) || reader.u64()? != hash(source) {
+		return Err(CacheError::Stale);
+	}
+	
+	let mut language = Directory::new();
+	
+	for _ in 0..reader.u32()? {
+		let key = reader.str()?;
+		let string = reader.str()?.to_owned();
+		let mut commands = Vec::new();
+		
+		for _ in 0..reader.u32()? {
+			let string_start = reader.u32()? as usize;
+			let arg_index = reader.u32()? as usize;
+			let command = reader.command()?;
+			
+			if !string.is_char_boundary(string_start) || commands.last().map(|last: &SimpleFormatCmd| last.string_start > string_start).unwrap_or(false) {
+				return Err(CacheError::Corrupt);
+			}
+			
+			commands.push(SimpleFormatCmd {
+				string_start: string_start,
+				arg_index: arg_index,
+				upper: command.upper,
+				// The command was checked when the cache was written, so it does not need to be checked again.
+				target: Target::from_unchecked(&command).map_err(|_| CacheError::Corrupt)?,
+				command: command
+			});
+		}
+		
+		language.insert(key, Ok(Compiled { string: string, commands: commands }));
+	}
+	
+	if reader.position != bytes.len() {
+		return Err(CacheError::Corrupt);
+	}
+	
+	Ok(language)
+}
+
+/// Reads the whole cache file, checking that it was created from the language file `source` in the format `format`.
+pub fn open<R>(file: &mut R, source: &[u8], format: Format) -> Result<Directory, CacheError> where R: Read {
+	let mut bytes = Vec::new();
+	file.read_to_end(&mut bytes).map_err(CacheError::Io)?;
+	
+	read(&bytes, source, format)
+}
+
+/// Loads a language file using the cache at `cache` if it is up to date. Otherwise, the language file is loaded normally and the cache is replaced.
+/// The cache is only an optimization, so failing to write it is not an error.
+pub fn load(source: &[u8], name: &str, cache: &Path) -> Result<Directory, Error> {
+	let format = format(source, name);
+	
+	if let Ok(language) = File::open(cache).map_err(CacheError::Io).and_then(|mut file| open(&mut file, source, format)) {
+		return Ok(language);
+	}
+	
+	let language = Directory::load(&mut &source[..], name)?;
+	
+	let _ = replace(&language, source, format, cache);
+	
+	Ok(language)
+}
+
+/// Writes the cache to a temporary file next to it, and then renames it over the old cache.
+/// Other clients may be reading the old cache at the same time, so it must never be truncated or written in place.
+fn replace(language: &Directory, source: &[u8], format: Format, cache: &Path) -> io::Result<()> {
+	let mut temporary = cache.as_os_str().to_owned();
+	temporary.push(format!(".{}.tmp", process::id()));
+	
+	let result = File::create(&temporary)
+		.and_then(|mut file| save(language, source, format, &mut file).and_then(|_| file.sync_all()))
+		.and_then(|_| fs::rename(&temporary, cache));
+	
+	if result.is_err() {
+		let _ = fs::remove_file(&temporary);
+	}
+	
+	result
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+	for shift in 0..4 {
+		out.push((value >> (shift * 8)) as u8);
+	}
+}
+
+fn push_u64(out: &mut Vec<u8>, value: u64) {
+	push_u32(out, value as u32);
+	push_u32(out, (value >> 32) as u32);
+}
+
+fn push_str(out: &mut Vec<u8>, value: &str) {
+	push_u32(out, value.len() as u32);
+	out.extend_from_slice(value.as_bytes());
+}
+
+/// Writes the parsed fields of a format command, so that reading the cache does not need to parse it again.
+/// The conversion is stored as its characters, which are always ASCII.
+fn push_command(out: &mut Vec<u8>, command: &FormatCommand) {
+	match command.index {
+		Index::Next => { out.push(0); push_u32(out, 0) },
+		Index::Exact(index) => { out.push(1); push_u32(out, index as u32) },
+		Index::Previous => { out.push(2); push_u32(out, 0) }
+	}
+	
+	out.push(command.flags.bits());
+	push_u32(out, command.width.map(|width| width as u32).unwrap_or(NONE));
+	push_u32(out, command.precision.map(|precision| precision as u32).unwrap_or(NONE));
+	out.push(command.kind.character(command.upper) as u8);
+	out.push(command.kind.second_character().map(|c| c as u8).unwrap_or(0));
+}
+
+/// Reads little endian values from the cache, failing if it ends too early.
+struct Reader<'a> {
+	bytes: &'a [u8],
+	position: usize
+}
+
+impl<'a> Reader<'a> {
+	fn bytes(&mut self, len: usize) -> Result<&'a [u8], CacheError> {
+		if self.bytes.len() - self.position < len {
+			return Err(CacheError::Corrupt);
+		}
+		
+		self.position += len;
+		
+		Ok(&self.bytes[self.position - len..self.position])
+	}
+	
+	fn u8(&mut self) -> Result<u8, CacheError> {
+		Ok(self.bytes(1)?[0])
+	}
+	
+	fn u32(&mut self) -> Result<u32, CacheError> {
+		Ok(self.bytes(4)?.iter().rev().fold(0, |value, &byte| (value << 8) | byte as u32))
+	}
+	
+	fn u64(&mut self) -> Result<u64, CacheError> {
+		let low = self.u32()? as u64;
+		let high = self.u32()? as u64;
+		
+		Ok(low | (high << 32))
+	}
+	
+	fn str(&mut self) -> Result<&'a str, CacheError> {
+		let len = self.u32()? as usize;
+		
+		str::from_utf8(self.bytes(len)?).map_err(|_| CacheError::Corrupt)
+	}
+	
+	fn optional(&mut self) -> Result<Option<usize>, CacheError> {
+		Ok(match self.u32()? {
+			NONE => None,
+			value => Some(value as usize)
+		})
+	}
+	
+	fn command(&mut self) -> Result<FormatCommand, CacheError> {
+		let index = match (self.u8()?, self.u32()?) {
+			(0, _) => Index::Next,
+			(1, index) => Index::Exact(index as usize),
+			(2, _) => Index::Previous,
+			_ => return Err(CacheError::Corrupt)
+		};
+		
+		let flags = Flags::from_bits(self.u8()?);
+		let width = self.optional()?;
+		let precision = self.optional()?;
+		
+		let (conversion, second) = (self.u8()? as char, self.u8()?);
+		let second = if second == 0 { None } else { Some(second as char) };
+		
+		let (kind, upper) = Kind::from_character(conversion, second).ok_or(CacheError::Corrupt)?;
+		
+		Ok(FormatCommand { index: index, flags: flags, width: width, precision: precision, kind: kind, upper: upper })
+	}
+}
+
+#[test]
+fn test_cache() {
+	let source = "menu.quit=Quit Game\nbroken=%y\nchat.type.text=<%s> %s\nstat=%2$,08d %1$S\n";
+	let language = Directory::load(&mut source.as_bytes(), "en_US.lang").unwrap();
+	
+	let mut cache = Vec::new();
+	save(&language, source.as_bytes(), Format::Lang, &mut cache).unwrap();
+	
+	assert_eq!(format(source.as_bytes(), "en_US.lang"), Format::Lang);
+	
+	let cached = read(&cache, source.as_bytes(), Format::Lang).unwrap();
+	
+	assert_eq!(cached.entries().len(), 3);
+	
+	for key in &["menu.quit", "chat.type.text", "stat"] {
+		assert_eq!(cached.get(key), language.get(key));
+	}
+	
+	// Translations that failed to load are left out, so they are still skipped by `lookup`.
+	assert!(cached.get("broken").is_none());
+	
+	match read(&cache, b"menu.quit=Quit\n", Format::Lang) {
+		Err(CacheError::Stale) => (),
+		other => panic!("expected a stale cache, got {:?}", other.map(|_| ()))
+	}
+	
+	// The same bytes are read differently as a JSON language file.
+	match read(&cache, source.as_bytes(), format(source.as_bytes(), "en_US.json")) {
+		Err(CacheError::Stale) => (),
+		other => panic!("expected a stale cache, got {:?}", other.map(|_| ()))
+	}
+	
+	match read(&cache[..cache.len() - 1], source.as_bytes(), Format::Lang) {
+		Err(CacheError::Corrupt) => (),
+		other => panic!("expected a corrupt cache, got {:?}", other.map(|_| ()))
+	}
+}
@@ -0,0 +1,1508 @@
+pub mod cache;
+
+use text::formatter::{Kind, FormatCommand, Index, ParseFormatError};
+use text::formatter::transform::{self, Target, Prim};
+use directory;
+use std::fmt::{self, Formatter, Display};
+use std::io::{self, Read, Write};
+use std::iter;
+use resource::{Asset, AssetSource};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use text::style::Style;
+use text::locale::NumberFormat;
+use text::repr::plain::PlainBuf;
+use text::repr::flat::ChatBuf;
+use serde_json::{self, Value};
+
+pub use text::formatter::time::Zone;
+
+pub type Directory = directory::Directory<Result<Compiled, LoadError>>;
+pub type Node = directory::Node<Result<Compiled, LoadError>>;
+
+impl Asset for Directory {
+	type Err = Error;
+	type WErr = io::Error;
+	
+	fn load<R>(read: &mut R, name: &str) -> Result<Self, Self::Err> where R: Read {
+		let mut source = String::new();
+		read.read_to_string(&mut source).map_err(Error::Io)?;
+		
+		let source = strip_bom(&source);
+		
+		match Format::detect(name, source) {
+			Format::Lang => load_lang(source, name),
+			Format::Json => load_json(source, name)
+		}
+	}
+	
+	fn save<W>(&self, write: &mut W) -> Result<(), Self::WErr> where W: Write {
+		// Entries that failed to compile are written back exactly as they were read.
+		// Malformed escapes only exist in the escaped format, where they are written without escaping them again.
+		let entries = self.entries().into_iter().map(|(key, value)| match *value {
+			Ok(ref compiled) => (key, compiled.source(), false),
+			Err(ref err) => (key, err.raw.clone(), err.err == ProcessError::MalformedEscape)
+		}).collect::<Vec<_>>();
+		
+		// The plain format has no way to write line breaks, or keys that would be read as comments or split in the wrong place.
+		let escaped = entries.iter().any(|&(ref key, ref value, verbatim)| 
+			verbatim || key.starts_with('#') || key.contains(|c| c == '=' || c == '\r' || c == '\n') || value.contains(|c| c == '\r' || c == '\n')
+		);
+		
+		if escaped {
+			writeln!(write, "{}", PARSE_ESCAPES)?;
+		}
+		
+		for (key, value, verbatim) in entries {
+			if !escaped {
+				writeln!(write, "{}={}", key, value)?
+			} else if verbatim {
+				writeln!(write, "{}={}", escape(&key, true), value)?
+			} else {
+				writeln!(write, "{}={}", escape(&key, true), escape(&value, false))?
+			}
+		}
+		
+		Ok(())
+	}
+}
+
+/// The locale that every other locale falls back to.
+pub const DEFAULT_LOCALE: &'static str = "en_US";
+
+/// Looks up a translation in each language in order, skipping the languages where it is missing or failed to load.
+pub fn lookup<'a>(languages: &'a [Directory], key: &str) -> Option<&'a Compiled> {
+	languages.iter().filter_map(|lang| match lang.get(key) {
+		Some(&Ok(ref compiled)) => Some(compiled),
+		_ => None
+	}).next()
+}
+
+/// A selected language along with the languages that it falls back to, most specific first.
+/// Keys that are missing from every language are shown as the key itself, like the vanilla client does.
+#[derive(Debug)]
+pub struct LanguageStack {
+	languages: Vec<Directory>
+}
+
+impl LanguageStack {
+	pub fn new() -> Self {
+		LanguageStack { languages: Vec::new() }
+	}
+	
+	/// Loads the selected locale followed by `DEFAULT_LOCALE` from `lang/<code>.json` or `lang/<code>.lang` in the source, 
+	/// trying the lowercase code used since 1.11 as well. Locales without a language file are skipped.
+	pub fn load<S>(source: &S, locale: &str) -> Result<Self, Error> where S: AssetSource<Directory> {
+		let mut stack = LanguageStack::new();
+		
+		let mut codes = vec![locale];
+		if !locale.eq_ignore_ascii_case(DEFAULT_LOCALE) {
+			codes.push(DEFAULT_LOCALE);
+		}
+		
+		for code in codes {
+			let names = [
+				format!("lang/{}.json", code), 
+				format!("lang/{}.lang", code), 
+				format!("lang/{}.json", code.to_lowercase()), 
+				format!("lang/{}.lang", code.to_lowercase())
+			];
+			
+			if let Some((mut ticket, name)) = names.iter().filter_map(|name| source.lookup(name).map(|ticket| (ticket, name))).next() {
+				stack.push(source.load(&mut ticket, name)?);
+			}
+		}
+		
+		Ok(stack)
+	}
+	
+	/// Adds a language to fall back to after the current ones.
+	pub fn push(&mut self, language: Directory) {
+		self.languages.push(language)
+	}
+	
+	pub fn languages(&self) -> &[Directory] {
+		&self.languages
+	}
+	
+	/// Returns the translation from the first language that has it.
+	pub fn get(&self, key: &str) -> Option<&Compiled> {
+		lookup(&self.languages, key)
+	}
+	
+	/// Returns the translation from the first language that has it, or the key itself if no language has it.
+	pub fn translate(&self, key: &str) -> Cow<Compiled> {
+		match self.get(key) {
+			Some(compiled) => Cow::Borrowed(compiled),
+			None => Cow::Owned(Compiled::literal(key))
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadError {
+	err: ProcessError,
+	file: String,
+	line: usize,
+	index: usize,
+	text: String,
+	/// The translation that failed to compile. For malformed escapes, this is the value as it was written in the file.
+	raw: String
+}
+
+/// Errors are equal if they report the same problem with the same translation, even if the entry moved to another line or file.
+impl PartialEq for LoadError {
+	fn eq(&self, other: &Self) -> bool {
+		self.err == other.err && self.raw == other.raw
+	}
+}
+
+impl LoadError {
+	pub fn error(&self) -> &ProcessError {
+		&self.err
+	}
+	
+	pub fn diagnostic(&self) -> Diagnostic {
+		Diagnostic {
+			level: Level::Error,
+			message: self.err.to_string(),
+			help: self.err.help(),
+			file: self.file.clone(),
+			line: self.line,
+			index: self.index,
+			text: self.text.clone()
+		}
+	}
+}
+
+impl Display for LoadError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		self.diagnostic().fmt(f)
+	}
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Level {
+	Error,
+	Warning
+}
+
+/// A problem found in a language file, which is displayed like the diagnostics of rustc. The line and index count from 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+	pub level: Level,
+	pub message: String,
+	pub help: Option<&'static str>,
+	pub file: String,
+	pub line: usize,
+	pub index: usize,
+	/// The text of the line with the problem.
+	pub text: String
+}
+
+impl Display for Diagnostic {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		match self.level {
+			Level::Error => writeln!(f, "error: {}", self.message)?,
+			Level::Warning => writeln!(f, "warning: {}", self.message)?
+		}
+		
+		writeln!(f, "  --> {}:{}:{}", self.file, self.line+1, self.index+1)?;
+				
+		let num = format!("{}", self.line+1);
+				
+		for _ in 0..num.len() {write!(f, " ")?};
+		writeln!(f, " |")?;
+					
+		writeln!(f, "{} | {}", num, self.text)?;
+					
+		for _ in 0..num.len() {write!(f, " ")?};
+		write!(f, " | ")?;
+		for _ in 0..self.index {
+			write!(f, " ")?
+		}
+						
+		writeln!(f, "^")?;
+		
+		for _ in 0..num.len() {write!(f, " ")?};
+		writeln!(f, " |")?;
+		
+		if let Some(help) = self.help {
+			for _ in 0..num.len() {write!(f, " ")?};
+			writeln!(f, " = help: {}", help)?
+		}
+		
+		Ok(())
+	}
+}
+
+#[derive(Debug)]
+pub enum Error {
+	Io(io::Error),
+	NoValue,
+	/// The JSON language file is not a flat object of strings. The line and column count from 0.
+	Syntax { line: usize, column: usize, expected: &'static str }
+}
+
+/// Some editors start files with a byte order mark, which isn't part of the first key.
+fn strip_bom(source: &str) -> &str {
+	if source.starts_with('\u{FEFF}') { &source['\u{FEFF}'.len_utf8()..] } else { source }
+}
+
+/// Checks a language file for every error and warning, instead of stopping at the first broken line or leaving errors in the entries of a `Directory`.
+/// Along with errors in the format codes, this warns about duplicate keys, keys with stray whitespace, and arguments that are used in conflicting ways.
+pub fn lint(source: &str, name: &str) -> Vec<Diagnostic> {
+	let source = strip_bom(source);
+	
+	let file = match Format::detect(name, source) {
+		Format::Lang => read_lang(source),
+		Format::Json => match read_json(source) {
+			Ok(file) => file,
+			Err(Error::Syntax { line, column, expected }) => return vec![Diagnostic {
+				level: Level::Error,
+				message: format!("expected {}", expected),
+				help: None,
+				file: name.to_owned(),
+				line: line,
+				index: column,
+				text: SourceLines::new(source).text(line).to_owned()
+			}],
+			Err(_) => unreachable!("reading JSON from a string only fails with syntax errors")
+		}
+	};
+	
+	let diagnostic = |level, message, help, position| {
+		let (line, index) = file.lines.locate(position);
+		
+		Diagnostic { level: level, message: message, help: help, file: name.to_owned(), line: line, index: index, text: file.lines.text(line).to_owned() }
+	};
+	
+	let mut diagnostics = Vec::new();
+	
+	for &line in &file.missing_values {
+		diagnostics.push(diagnostic(
+			Level::Error, 
+			"expected '=' after the key".to_owned(), 
+			Some("the vanilla client skips lines without '=', start the line with '#' to make it a comment"),
+			file.lines.start(line) + file.lines.text(line).len()
+		));
+	}
+	
+	let mut seen = HashMap::new();
+	
+	for entry in &file.entries {
+		let key_line = file.lines.locate(entry.key_position).0;
+		
+		if entry.key.trim() != entry.key {
+			diagnostics.push(diagnostic(
+				Level::Warning, 
+				format!("key {:?} has leading or trailing whitespace", entry.key), 
+				Some("the whitespace is part of the key, so the translation will not be found under the key without it"),
+				entry.key_position
+			));
+		}
+		
+		if let Some(first_line) = seen.insert(&entry.key[..], key_line) {
+			diagnostics.push(diagnostic(
+				Level::Warning, 
+				format!("duplicate key {:?}, replacing the translation on line {}", entry.key, first_line + 1), 
+				Some("only the last translation of a key is used"),
+				entry.key_position
+			));
+		}
+		
+		let (compiled, positions) = match file.compile(entry, name) {
+			Ok(compiled) => compiled,
+			Err(err) => {
+				diagnostics.push(err.diagnostic());
+				continue;
+			}
+		};
+		
+		let mut kinds: HashMap<usize, Vec<Kind>> = HashMap::new();
+		
+		for (cmd, &position) in compiled.commands.iter().zip(positions.iter()) {
+			let kind = cmd.command.kind;
+			let previous = kinds.entry(cmd.arg_index).or_insert_with(Vec::new);
+			
+			if let Some(&first) = previous.iter().find(|first| !first.shares_argument_type(kind)) {
+				diagnostics.push(diagnostic(
+					Level::Warning,
+					format!("argument {} is formatted as both %{} and %{}", cmd.arg_index + 1, first.character(false), kind.character(false)),
+					Some("no argument type can be formatted by both of these format codes"),
+					position
+				));
+			}
+			
+			previous.push(kind);
+		}
+		
+		let explicit = |cmd: &SimpleFormatCmd| match cmd.command.index {
+			Index::Exact(_) => Some(true),
+			Index::Next => Some(false),
+			Index::Previous => None
+		};
+		
+		let first = compiled.commands.iter().filter_map(&explicit).next();
+		
+		if let Some((_, &position)) = compiled.commands.iter().zip(positions.iter()).find(|&(cmd, _)| explicit(cmd).is_some() && explicit(cmd) != first) {
+			diagnostics.push(diagnostic(
+				Level::Warning,
+				"relative (%s) and explicit (%1$s) argument indexes are mixed".to_owned(),
+				Some("relative indexes ignore explicit ones, so in \"%2$s %s\" both format codes refer to different arguments"),
+				position
+			));
+		}
+	}
+	
+	diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.index));
+	diagnostics
+}
+
+/// The format of a language file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Format {
+	/// `key=value` lines, used before 1.13.
+	Lang,
+	/// A flat JSON object of keys to values, used since 1.13.
+	Json
+}
+
+impl Format {
+	/// Detects the format from the extension of the file name, or from the content if the extension is unknown.
+	pub fn detect(name: &str, source: &str) -> Self {
+		if name.ends_with(".json") {
+			Format::Json
+		} else if name.ends_with(".lang") {
+			Format::Lang
+		} else if source.trim().starts_with('{') {
+			// Keys of .lang files never start with '{' in practice, while JSON language files always do.
+			Format::Json
+		} else {
+			Format::Lang
+		}
+	}
+}
+
+/// The first line of a .lang file that uses the escapes and line continuations of Java's properties files, as introduced by Forge.
+const PARSE_ESCAPES: &'static str = "#PARSE_ESCAPES";
+
+/// Loads a language file in the `key=value` format. Like the vanilla loader, the value runs to the end of the line after the first '='.
+/// Lines without a '=' are skipped like the vanilla loader does, and are reported by `lint` instead.
+/// If the first line is `#PARSE_ESCAPES`, the file is instead read like a Java properties file, with escapes and line continuations.
+pub fn load_lang(source: &str, name: &str) -> Result<Directory, Error> {
+	Ok(read_lang(source).into_directory(name))
+}
+
+/// Loads a language file in the JSON format, which must be a single object with string values.
+pub fn load_json(source: &str, name: &str) -> Result<Directory, Error> {
+	Ok(read_json(source)?.into_directory(name))
+}
+
+/// A key and translation read from a language file, before the translation is compiled.
+struct RawEntry {
+	key: String,
+	/// The position of the key in the source.
+	key_position: usize,
+	value: String,
+	/// The source position of every byte of the value, with an extra entry for the end of the value.
+	offsets: Vec<usize>,
+	/// The position of the first malformed escape, along with the value as it was written.
+	malformed: Option<(usize, String)>
+}
+
+/// The entries of a language file in the order that they were written.
+struct RawFile<'a> {
+	lines: SourceLines<'a>,
+	entries: Vec<RawEntry>,
+	/// The lines of a plain .lang file that are not comments, but do not have a '=' either.
+	missing_values: Vec<usize>
+}
+
+impl<'a> RawFile<'a> {
+	/// Compiles the translation of the entry, reporting errors at the right place in the file. 
+	/// On success, the source position of each format code is returned along with the translation.
+	fn compile(&self, entry: &RawEntry, name: &str) -> Result<(Compiled, Vec<usize>), LoadError> {
+		if let Some((position, ref written)) = entry.malformed {
+			return Err(self.lines.load_error(ProcessError::MalformedEscape, name, position, written));
+		}
+		
+		match Compiled::compile_with_positions(&entry.value) {
+			Ok((compiled, positions)) => Ok((compiled, positions.into_iter().map(|index| entry.offsets[index]).collect())),
+			Err((index, err)) => Err(self.lines.load_error(err, name, entry.offsets[index], &entry.value))
+		}
+	}
+	
+	fn into_directory(self, name: &str) -> Directory {
+		let mut dir = Directory::new();
+		
+		for entry in &self.entries {
+			dir.insert(&entry.key, self.compile(entry, name).map(|(compiled, _)| compiled));
+		}
+		
+		dir
+	}
+}
+
+fn read_lang(source: &str) -> RawFile {
+	let lines = SourceLines::new(source);
+	
+	if lines.len() > 0 && lines.text(0).trim() == PARSE_ESCAPES {
+		return read_properties(lines);
+	}
+	
+	let mut file = RawFile { lines: lines, entries: Vec::new(), missing_values: Vec::new() };
+	
+	for line_number in 0..file.lines.len() {
+		let line = file.lines.text(line_number);
+		let line_start = file.lines.start(line_number);
+		
+		match parse_line(line) {
+			Ok(Some((key, raw))) => {
+				let value_start = line_start + key.len() + 1;
+				
+				file.entries.push(RawEntry {
+					key: key.to_owned(),
+					key_position: line_start,
+					value: raw.to_owned(),
+					offsets: (value_start..value_start + raw.len() + 1).collect(),
+					malformed: None
+				})
+			},
+			Ok(None) => (),
+			Err(_) => file.missing_values.push(line_number)
+		}
+	}
+	
+	file
+}
+
+/// Reads a .lang file with the rules of `java.util.Properties`: keys end at the first unescaped '=', ':' or whitespace, 
+/// lines starting with '#' or '!' are comments, a backslash at the end of a line continues the entry on the next line,
+/// and values may contain escapes such as `\n`, `\t` and `\u00e9`.
+fn read_properties(lines: SourceLines) -> RawFile {
+	let mut reader = PropertiesReader { source: lines.source, position: 0 };
+	let mut file = RawFile { lines: lines, entries: Vec::new(), missing_values: Vec::new() };
+	
+	while let Some(line) = reader.logical_line() {
+		let is_whitespace = |c: char| c == ' ' || c == '\t' || c == '\u{C}';
+		
+		let mut key_end = line.len();
+		let mut value_start = line.len();
+		let mut has_separator = false;
+		let mut backslash = false;
+		
+		for (index, &(_, c)) in line.iter().enumerate() {
+			if !backslash && (c == '=' || c == ':' || is_whitespace(c)) {
+				key_end = index;
+				value_start = index + 1;
+				has_separator = c == '=' || c == ':';
+				break;
+			}
+			
+			backslash = c == '\\' && !backslash;
+		}
+		
+		// Whitespace around the separator is skipped, and a single '=' or ':' may follow whitespace that ended the key.
+		while let Some(&(_, c)) = line.get(value_start) {
+			if !has_separator && (c == '=' || c == ':') {
+				has_separator = true;
+			} else if !is_whitespace(c) {
+				break;
+			}
+			
+			value_start += 1;
+		}
+		
+		let line_end = line.last().map(|&(position, c)| position + c.len_utf8()).unwrap_or(reader.position);
+		let key_end_position = line.get(key_end).map(|&(position, _)| position).unwrap_or(line_end);
+		
+		let key = unescape(&line[..key_end], key_end_position);
+		let value = unescape(&line[value_start..], line_end);
+		
+		let malformed = key.malformed.or(value.malformed).map(|position| {
+			(position, line[value_start..].iter().map(|&(_, c)| c).collect::<String>())
+		});
+		
+		file.entries.push(RawEntry {
+			key: key.string,
+			key_position: line.first().map(|&(position, _)| position).unwrap_or(line_end),
+			value: value.string,
+			offsets: value.offsets,
+			malformed: malformed
+		});
+	}
+	
+	file
+}
+
+/// Reads the logical lines of a properties file, joining continued lines and skipping comments and blank lines.
+struct PropertiesReader<'a> {
+	source: &'a str,
+	position: usize
+}
+
+impl<'a> PropertiesReader<'a> {
+	fn peek(&self) -> Option<char> {
+		self.source[self.position..].chars().next()
+	}
+	
+	fn skip<F>(&mut self, skip: F) where F: Fn(char) -> bool {
+		self.position += self.source[self.position..].chars().take_while(|&c| skip(c)).map(char::len_utf8).sum::<usize>();
+	}
+	
+	/// Returns the characters of the next logical line along with their positions in the source.
+	fn logical_line(&mut self) -> Option<Vec<(usize, char)>> {
+		loop {
+			self.skip(|c| c == ' ' || c == '\t' || c == '\u{C}' || c == '\r' || c == '\n');
+			
+			match self.peek() {
+				Some('#') | Some('!') => self.skip(|c| c != '\r' && c != '\n'),
+				Some(_) => break,
+				None => return None
+			}
+		}
+		
+		let mut line = Vec::new();
+		let mut backslashes = 0;
+		
+		while let Some(c) = self.peek() {
+			if c == '\r' || c == '\n' {
+				if backslashes % 2 == 0 {
+					break;
+				}
+				
+				// An odd amount of backslashes means that the last one escapes the line break, continuing the line.
+				line.pop();
+				backslashes = 0;
+				
+				self.position += if self.source[self.position..].starts_with("\r\n") {2} else {1};
+				self.skip(|c| c == ' ' || c == '\t' || c == '\u{C}');
+				
+				continue;
+			}
+			
+			line.push((self.position, c));
+			self.position += c.len_utf8();
+			
+			backslashes = if c == '\\' { backslashes + 1 } else { 0 };
+		}
+		
+		// Like a line break, the end of the file swallows a trailing backslash.
+		if backslashes % 2 == 1 {
+			line.pop();
+		}
+		
+		Some(line)
+	}
+}
+
+/// A key or value of a properties file after escapes have been decoded.
+struct Unescaped {
+	string: String,
+	/// The source position of every byte of the string, with an extra entry for the end of the string.
+	offsets: Vec<usize>,
+	/// The position of the first malformed unicode escape.
+	malformed: Option<usize>
+}
+
+fn unescape(chars: &[(usize, char)], end: usize) -> Unescaped {
+	let mut unescaped = Unescaped { string: String::new(), offsets: Vec::new(), malformed: None };
+	let mut index = 0;
+	
+	let hex = |from: usize| -> Option<u32> {
+		let digits = chars.get(from..from + 4)?;
+		digits.iter().fold(Some(0), |value, &(_, c)| Some(value? * 16 + c.to_digit(16)?))
+	};
+	
+	while let Some(&(position, c)) = chars.get(index) {
+		index += 1;
+		
+		let c = if c != '\\' { c } else {
+			let escaped = match chars.get(index) {
+				Some(&(_, escaped)) => escaped,
+				None => break
+			};
+			
+			index += 1;
+			
+			match escaped {
+				't' => '\t',
+				'n' => '\n',
+				'r' => '\r',
+				'f' => '\u{C}',
+				'u' => {
+					let high = hex(index);
+					let is_pair = chars.get(index + 4).map(|&(_, c)| c) == Some('\\') && chars.get(index + 5).map(|&(_, c)| c) == Some('u');
+					
+					let decoded = match (high, if is_pair { hex(index + 6) } else { None }) {
+						(Some(high), Some(low)) if high >= 0xD800 && high < 0xDC00 && low >= 0xDC00 && low < 0xE000 => {
+							index += 6;
+							::std::char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+						},
+						(Some(value), _) => ::std::char::from_u32(value),
+						(None, _) => None
+					};
+					
+					match decoded {
+						Some(c) => {
+							index += 4;
+							c
+						},
+						None => {
+							// Keep the escape as it was written, the entry is reported as an error anyways.
+							unescaped.malformed = unescaped.malformed.or(Some(position));
+							unescaped.string.push('\\');
+							unescaped.offsets.push(position);
+							'u'
+						}
+					}
+				},
+				other => other
+			}
+		};
+		
+		unescaped.string.push(c);
+		unescaped.offsets.extend(iter::repeat(position).take(c.len_utf8()));
+	}
+	
+	unescaped.offsets.push(end);
+	unescaped
+}
+
+/// Escapes a key or value so that it can be read back from a file with the `#PARSE_ESCAPES` header, like `Properties::store`.
+fn escape(string: &str, key: bool) -> String {
+	let mut escaped = String::with_capacity(string.len());
+	
+	for (index, c) in string.chars().enumerate() {
+		match c {
+			' ' if key || index == 0 => escaped.push_str("\\ "),
+			'\\' => escaped.push_str("\\\\"),
+			'\t' => escaped.push_str("\\t"),
+			'\n' => escaped.push_str("\\n"),
+			'\r' => escaped.push_str("\\r"),
+			'\u{C}' => escaped.push_str("\\f"),
+			'=' | ':' | '#' | '!' => {
+				escaped.push('\\');
+				escaped.push(c);
+			},
+			c => escaped.push(c)
+		}
+	}
+	
+	escaped
+}
+
+/// The lines of a language file, ending at "\n", "\r\n" or "\r" like they do for Java's `BufferedReader`.
+struct SourceLines<'a> {
+	source: &'a str,
+	starts: Vec<usize>
+}
+
+impl<'a> SourceLines<'a> {
+	fn new(source: &'a str) -> Self {
+		let mut starts = if source.is_empty() { Vec::new() } else { vec![0] };
+		let bytes = source.as_bytes();
+		
+		for (index, &byte) in bytes.iter().enumerate() {
+			let ends_line = byte == b'\n' || (byte == b'\r' && bytes.get(index + 1) != Some(&b'\n'));
+			
+			if ends_line && index + 1 < bytes.len() {
+				starts.push(index + 1);
+			}
+		}
+		
+		SourceLines { source: source, starts: starts }
+	}
+	
+	fn len(&self) -> usize {
+		self.starts.len()
+	}
+	
+	fn start(&self, line: usize) -> usize {
+		self.starts[line]
+	}
+	
+	/// Returns the text of the line, without the line break.
+	fn text(&self, line: usize) -> &'a str {
+		let rest = match self.starts.get(line) {
+			Some(&start) => &self.source[start..],
+			None => return ""
+		};
+		
+		&rest[..rest.find(|c| c == '\r' || c == '\n').unwrap_or(rest.len())]
+	}
+	
+	/// Finds the line and column of a position in the source, both counting from 0.
+	fn locate(&self, position: usize) -> (usize, usize) {
+		let line = match self.starts.binary_search(&position) {
+			Ok(line) => line,
+			Err(0) => return (0, position),
+			Err(next) => next - 1
+		};
+		
+		(line, position - self.starts[line])
+	}
+	
+	fn load_error(&self, err: ProcessError, name: &str, position: usize, raw: &str) -> LoadError {
+		let (line, column) = self.locate(position);
+		
+		LoadError {
+			err: err,
+			file: name.to_owned(),
+			line: line,
+			text: self.text(line).to_owned(),
+			index: column,
+			raw: raw.to_owned()
+		}
+	}
+}
+
+fn read_json(source: &str) -> Result<RawFile, Error> {
+	let mut entries = Vec::new();
+	let mut scanner = JsonScanner { source: source, position: 0, lines: SourceLines::new(source) };
+	
+	scanner.expect('{', "'{' to start the language object")?;
+	
+	if !scanner.consume('}') {
+		loop {
+			let (key, key_offsets) = scanner.string("a string key")?;
+			scanner.expect(':', "':' after the key")?;
+			let (value, offsets) = scanner.string("a string value")?;
+			
+			entries.push(RawEntry { key: key, key_position: key_offsets[0], value: value, offsets: offsets, malformed: None });
+			
+			if scanner.consume('}') {
+				break;
+			}
+			
+			scanner.expect(',', "',' or '}' after the value")?;
+		}
+	}
+	
+	scanner.skip_whitespace();
+	
+	if scanner.position != source.len() {
+		return Err(scanner.error("the end of the file after the language object"));
+	}
+	
+	Ok(RawFile { lines: scanner.lines, entries: entries, missing_values: Vec::new() })
+}
+
+/// A minimal JSON reader for flat language objects that remembers where every character of a string came from,
+/// so that errors in translations can point to the right place in the file.
+struct JsonScanner<'a> {
+	source: &'a str,
+	position: usize,
+	lines: SourceLines<'a>
+}
+
+impl<'a> JsonScanner<'a> {
+	fn skip_whitespace(&mut self) {
+		self.position += self.source[self.position..].bytes().take_while(|&b| b == b' ' || b == b'\t' || b == b'\n' || b == b'\r').count();
+	}
+	
+	fn peek(&self) -> Option<char> {
+		self.source[self.position..].chars().next()
+	}
+	
+	/// Skips whitespace, then consumes the character if it is next.
+	fn consume(&mut self, expected: char) -> bool {
+		self.skip_whitespace();
+		
+		if self.peek() == Some(expected) {
+			self.position += expected.len_utf8();
+			true
+		} else {
+			false
+		}
+	}
+	
+	fn expect(&mut self, expected: char, description: &'static str) -> Result<(), Error> {
+		if self.consume(expected) { Ok(()) } else { Err(self.error(description)) }
+	}
+	
+	/// Reads a string, returning it along with the source position of every byte of it. 
+	/// The positions contain an extra entry for the end of the string.
+	fn string(&mut self, description: &'static str) -> Result<(String, Vec<usize>), Error> {
+		self.expect('"', description)?;
+		
+		let mut string = String::new();
+		let mut offsets = Vec::new();
+		
+		loop {
+			let start = self.position;
+			let c = self.peek().ok_or_else(|| self.error("'\"' to end the string"))?;
+			self.position += c.len_utf8();
+			
+			let c = match c {
+				'"' => break,
+				'\\' => self.escape()?,
+				c if (c as u32) < 0x20 => {
+					self.position = start;
+					return Err(self.error("an escape sequence instead of a control character"))
+				},
+				c => c
+			};
+			
+			string.push(c);
+			offsets.extend(iter::repeat(start).take(c.len_utf8()));
+		}
+		
+		offsets.push(self.position - 1);
+		
+		Ok((string, offsets))
+	}
+	
+	fn escape(&mut self) -> Result<char, Error> {
+		let c = self.peek().ok_or_else(|| self.error("an escape sequence"))?;
+		self.position += c.len_utf8();
+		
+		Ok(match c {
+			'"' => '"',
+			'\\' => '\\',
+			'/' => '/',
+			'b' => '\u{8}',
+			'f' => '\u{C}',
+			'n' => '\n',
+			'r' => '\r',
+			't' => '\t',
+			'u' => {
+				let high = self.hex()?;
+				
+				if high >= 0xD800 && high < 0xDC00 {
+					// A surrogate pair, used for characters outside of the basic multilingual plane.
+					if !self.source[self.position..].starts_with("\\u") {
+						return Err(self.error("a low surrogate after the high surrogate"));
+					}
+					
+					self.position += 2;
+					let low = self.hex()?;
+					
+					if low < 0xDC00 || low >= 0xE000 {
+						return Err(self.error("a low surrogate after the high surrogate"));
+					}
+					
+					::std::char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)).expect("surrogate pairs always form a valid character")
+				} else {
+					::std::char::from_u32(high).ok_or_else(|| self.error("a high surrogate before the low surrogate"))?
+				}
+			},
+			_ => {
+				self.position -= c.len_utf8();
+				return Err(self.error("a valid escape sequence"))
+			}
+		})
+	}
+	
+	fn hex(&mut self) -> Result<u32, Error> {
+		let digits = match self.source[self.position..].get(..4) {
+			Some(digits) if digits.chars().all(|c| c.is_digit(16)) => digits,
+			_ => return Err(self.error("4 hex digits"))
+		};
+		
+		let value = u32::from_str_radix(digits, 16).expect("4 hex digits always fit in a u32");
+		
+		self.position += 4;
+		Ok(value)
+	}
+	
+	fn error(&self, expected: &'static str) -> Error {
+		let (line, column) = self.lines.locate(self.position);
+		
+		Error::Syntax { line: line, column: column, expected: expected }
+	}
+}
+
+pub fn parse_line(line: &str) -> Result<Option<(&str, &str)>, Error> {
+	if line.is_empty() || line.starts_with("#") {
+		// Comment
+		return Ok(None);
+	}
+	
+	let mut items = line.splitn(2, "=");
+	
+	Ok(Some((
+		items.next().expect("A split iterator should yield at least one element! Go home Rust, you're drunk."), 
+		items.next().ok_or(Error::NoValue)?
+	)))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SimpleFormatCmd {
+	string_start: usize,
+	arg_index: usize,
+	upper: bool,
+	target: Target,
+	/// The format code as it was written, used to restore it when saving.
+	command: FormatCommand
+}
+
+impl Display for SimpleFormatCmd {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		if self.upper {
+			write!(f, "{{{}::to_uppercase}}", self.arg_index)
+		} else {
+			write!(f, "{{{}}}", self.arg_index)
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessError {
+	/// The flags, width or precision of the format code can't be used together.
+	Target(transform::Error),
+	/// A unicode escape in a file with the `#PARSE_ESCAPES` header does not have 4 hex digits, or is an unpaired surrogate.
+	MalformedEscape,
+	Parse(ParseFormatError),
+	NoPreviousArgument
+}
+
+impl ProcessError {
+	pub fn help(&self) -> Option<&'static str> {
+		match self {
+			&ProcessError::Target(ref e) 		 => e.help(),
+			&ProcessError::MalformedEscape 		 => Some("unicode escapes are written with exactly 4 hex digits, such as \\u00e9"),
+			&ProcessError::Parse(ref p) 			 => p.help(),
+			&ProcessError::NoPreviousArgument 	 => Some("please use relative indexing (%s) or exact indexing (%1$s) for this format code")
+		}
+	}
+}
+
+impl Display for ProcessError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		match self {
+			&ProcessError::Target(ref e) => write!(f, "invalid format code: {}", e),
+			&ProcessError::MalformedEscape => write!(f, "malformed \\uXXXX escape"),
+			&ProcessError::Parse(ref p) => write!(f, "while parsing format: {}", p),
+			&ProcessError::NoPreviousArgument => write!(f, "previous argument flag used ('<'), but there is no previous argument")
+		}
+	}
+}
+
+struct CmdProcessor {
+	head: usize,
+	last: Option<usize>
+}
+
+impl CmdProcessor {
+	fn new() -> Self {
+		CmdProcessor {
+			head: 1,
+			last: None
+		}
+	}
+	
+	fn process(&mut self, string_start: usize, cmd: FormatCommand) -> Result<SimpleFormatCmd, ProcessError> {
+		let target = Target::from(&cmd).map_err(ProcessError::Target)?;
+		
+		let current_idx = match cmd.index {
+			Index::Previous => if let Some(last) = self.last {last} else {return Err(ProcessError::NoPreviousArgument)},
+			Index::Exact(idx) => idx,
+			Index::Next => self.head
+		};
+		
+		if cmd.index == Index::Next {
+			self.head += 1;
+		}
+		
+		self.last = Some(current_idx);
+		
+		Ok(SimpleFormatCmd {
+				string_start: string_start,
+				arg_index: current_idx - 1, // Format string indices count from 1, but array indices count from 0.
+				upper: cmd.upper,
+				target: target,
+				command: cmd
+		})	
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compiled {
+	string: String,
+	commands: Vec<SimpleFormatCmd>
+}
+
+impl Compiled {
+	/// Creates a translation that is always the text, without any format codes.
+	pub fn literal(text: &str) -> Self {
+		Compiled { string: text.to_owned(), commands: Vec::new() }
+	}
+	
+	pub fn compile(source: &str) -> Result<Self, (usize, ProcessError)> {
+		Self::compile_with_positions(source).map(|(compiled, _)| compiled)
+	}
+	
+	/// Compiles the translation, also returning the position of each format code that refers to an argument in the source.
+	fn compile_with_positions(source: &str) -> Result<(Self, Vec<usize>), (usize, ProcessError)> {
+		let mut processor = CmdProcessor::new();
+		let mut compiled = Compiled { string: String::new(), commands: Vec::new() };
+		let mut positions = Vec::new();
+		
+		let mut next = 0;
+		
+		for (index, c) in source.char_indices() {
+			if index < next { continue };
+			
+			match c {
+				'%' => {
+					let (size, cmd) = FormatCommand::parse(&source[index..]).map_err(|e| (index, ProcessError::Parse(e)))?; 
+					
+					next = index + size;
+					
+					if cmd.kind == Kind::Newline {
+						compiled.string.push('\n');
+					} else if cmd.kind == Kind::Percent {
+						// Like Java, the width of %% is honored, such as %-3% becoming "%  ".
+						let padding = iter::repeat(' ').take(cmd.width.unwrap_or(1).saturating_sub(1)).collect::<String>();
+						
+						if cmd.flags.left_justify() {
+							compiled.string.push('%');
+							compiled.string.push_str(&padding);
+						} else {
+							compiled.string.push_str(&padding);
+							compiled.string.push('%');
+						}
+					} else {
+						compiled.commands.push(processor.process(compiled.string.len(), cmd).map_err(|e| (index, e))?);
+						positions.push(index);
+					}
+				},
+				c => compiled.string.push(c)
+			}
+		}
+		
+		Ok((compiled, positions))
+	}
+}
+
+/// A part of a compiled translation: either literal text, or a reference to one of the arguments.
+/// The target describes the width, precision, and conversion of the format code.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Part<'a> {
+	Text(&'a str),
+	Arg { index: usize, upper: bool, target: &'a Target }
+}
+
+pub struct Parts<'a> {
+	compiled: &'a Compiled,
+	position: usize,
+	command: usize
+}
+
+impl<'a> Iterator for Parts<'a> {
+	type Item = Part<'a>;
+	
+	fn next(&mut self) -> Option<Self::Item> {
+		let command = self.compiled.commands.get(self.command);
+		let end = command.map(|cmd| cmd.string_start).unwrap_or(self.compiled.string.len());
+		
+		if self.position < end {
+			let text = &self.compiled.string[self.position..end];
+			self.position = end;
+			
+			return Some(Part::Text(text));
+		}
+		
+		command.map(|cmd| {
+			self.command += 1;
+			
+			Part::Arg { index: cmd.arg_index, upper: cmd.upper, target: &cmd.target }
+		})
+	}
+}
+
+/// An argument of a translation.
+#[derive(Debug, Copy, Clone)]
+pub enum Arg<'a> {
+	/// Unstyled text, which takes the style of the translation.
+	Str(&'a str),
+	/// Styled text, which keeps its own styles.
+	Plain(&'a PlainBuf),
+	/// An element of the `with` array of a chat component. Primitives take the style of the translation, while chat components inherit the properties they do not set from it.
+	/// Chat components are only written as styled text: their click and hover events are dropped, and nested translations are written as their key.
+	/// Use `text::process::Processor` to translate whole chat components instead.
+	Json(&'a Value)
+}
+
+impl<'a> Arg<'a> {
+	/// Returns the argument as styled text, where unstyled text has the style of the translation.
+	fn text(&self, style: Style) -> Result<PlainBuf, FormatError> {
+		let mut text = PlainBuf::new();
+		
+		match *self {
+			Arg::Str(string) => text.push(string, style),
+			Arg::Plain(plain) => text.append(plain),
+			Arg::Json(value) => match *value {
+				Value::String(ref string) => text.push(string, style),
+				// Like in vanilla, chat components inherit the style of the translation.
+				Value::Array(_) | Value::Object(_) => text = ChatBuf::from_value_in(value, style).map_err(FormatError::Component)?.to_plain().value,
+				Value::Null => return Err(FormatError::Null),
+				// Other primitives are written as they appear in JSON, such as `true` or `2.5`.
+				_ => text.push(&value.to_string(), style)
+			}
+		}
+		
+		Ok(text)
+	}
+	
+	fn write(&self, target: &mut PlainBuf, style: Style, format: &Target, upper: bool, numbers: &NumberFormat, zone: &Zone) -> Result<(), FormatError> {
+		let mut text = self.text(style)?;
+		
+		if !format.is_text() {
+			let prim = match *self {
+				Arg::Json(value) => Prim::from_json(value),
+				_ => None
+			}.unwrap_or_else(|| Prim::String(text.unstyled().to_owned()));
+			
+			// Numbers take the style of the first character of the argument.
+			let number_style = text.iter().next().map(|(_, style)| style).unwrap_or(style);
+			
+			// Java would throw an exception for arguments that the conversion does not accept, such as %d with "three".
+			// Instead, they are inserted as text, like %s, which is what Minecraft does for plain %d and %f codes.
+			if let Some(formatted) = format.format_in(&prim, upper, numbers, zone) {
+				target.push(&formatted, number_style);
+				return Ok(());
+			}
+		}
+		
+		if let Some(max) = format.max_chars() {
+			text.truncate_chars(max);
+		}
+		
+		let (before, after) = format.padding(text.unstyled().chars().count());
+		
+		for _ in 0..before { target.push(" ", style) }
+		
+		for (text, style) in text.iter() {
+			target.push(&if upper { text.to_uppercase() } else { text.to_owned() }, style)
+		}
+		
+		for _ in 0..after { target.push(" ", style) }
+		
+		Ok(())
+	}
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+	/// The translation refers to an argument that was not provided. Contains the index of the argument, counting from 0.
+	MissingArgument(usize),
+	/// A JSON argument is not a valid chat component.
+	Component(serde_json::Error),
+	/// A JSON argument is null, which is neither text nor a chat component.
+	Null
+}
+
+impl Display for FormatError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			FormatError::MissingArgument(index) => write!(f, "the translation refers to argument {}, but it was not provided", index + 1),
+			FormatError::Component(ref err) => write!(f, "invalid chat component argument: {}", err),
+			FormatError::Null => write!(f, "an argument is null")
+		}
+	}
+}
+
+impl Compiled {
+	/// Formats the translation with the arguments, inserting each argument where its format code was. 
+	/// The text of the translation itself has the style `style`. Numbers are written like en_US, and times, such as the argument of %tH, are written in UTC.
+	/// There is no language to look up nested translations in, so see `Arg::Json` for how chat component arguments are written.
+	pub fn format(&self, style: Style, args: &[Arg]) -> Result<PlainBuf, FormatError> {
+		self.format_in(style, args, &NumberFormat::en_us(), &Zone::utc())
+	}
+	
+	/// Formats the translation like `format`, writing numbers with the separators of the number format and times in the zone. 
+	/// Times are given as milliseconds since the Unix epoch.
+	pub fn format_in(&self, style: Style, args: &[Arg], numbers: &NumberFormat, zone: &Zone) -> Result<PlainBuf, FormatError> {
+		let mut target = PlainBuf::new();
+		
+		for part in self.parts() {
+			match part {
+				Part::Text(text) => target.push(text, style),
+				Part::Arg { index, upper, target: format } => args.get(index).ok_or(FormatError::MissingArgument(index))?.write(&mut target, style, format, upper, numbers, zone)?
+			}
+		}
+		
+		Ok(target)
+	}
+	
+	/// Returns the translation as it would be written in a language file, with the original format codes restored.
+	/// Literal percent signs and line breaks are escaped as %% and %n, so compiling the source again results in the same translation.
+	pub fn source(&self) -> String {
+		let mut source = String::with_capacity(self.string.len());
+		let mut position = 0;
+		
+		for cmd in self.commands.iter().map(Some).chain(Some(None)) {
+			let end = cmd.map(|cmd| cmd.string_start).unwrap_or(self.string.len());
+			
+			for c in self.string[position..end].chars() {
+				match c {
+					'%' => source.push_str("%%"),
+					'\n' => source.push_str("%n"),
+					c => source.push(c)
+				}
+			}
+			
+			if let Some(cmd) = cmd {
+				source.push_str(&cmd.command.to_string());
+			}
+			
+			position = end;
+		}
+		
+		source
+	}
+	
+	/// Replaces the literal text of the translation, keeping the format codes in place.
+	/// The function is called with the text before each format code and the text after the last one, along with the number of format codes before the text.
+	/// It is called for every run of text, even if it is empty, so it can also add text to the start or the end of the translation.
+	pub fn map_text<F>(&self, mut f: F) -> Self where F: FnMut(usize, &str) -> String {
+		let mut mapped = Compiled { string: String::with_capacity(self.string.len()), commands: Vec::with_capacity(self.commands.len()) };
+		let mut position = 0;
+		
+		for (run, cmd) in self.commands.iter().map(Some).chain(Some(None)).enumerate() {
+			let end = cmd.map(|cmd| cmd.string_start).unwrap_or(self.string.len());
+			
+			mapped.string.push_str(&f(run, &self.string[position..end]));
+			
+			if let Some(cmd) = cmd {
+				mapped.commands.push(SimpleFormatCmd { string_start: mapped.string.len(), .. cmd.clone() });
+			}
+			
+			position = end;
+		}
+		
+		mapped
+	}
+	
+	/// Returns the literal text and the argument references of the translation, in order.
+	pub fn parts(&self) -> Parts {
+		Parts {
+			compiled: self,
+			position: 0,
+			command: 0
+		}
+	}
+}
+
+impl Display for Compiled {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		let mut base = 0;
+		let mut string = self.string.clone();
+		
+		for cmd in self.commands.iter() {
+			let command = format!("{}", cmd);
+			string.insert_str(base + cmd.string_start, &command);
+			base += command.len();
+		}
+		
+		write!(f, "\"{}\"", string)
+	}
+}
+
+#[test]
+fn test_parse_lines() {
+	assert_eq!(Some(("translation.test.none", "Hello, world!")), parse_line("translation.test.none=Hello, world!").unwrap());
+	assert_eq!(Some(("translation.test.none", "Hello, world!=whatever")), parse_line("translation.test.none=Hello, world!=whatever").unwrap());
+	assert_eq!(Some((" translation.test.none", "Hello, world! ")), parse_line(" translation.test.none=Hello, world! ").unwrap());
+	assert_eq!(None, parse_line("# This is an interesting comment.").unwrap());
+	assert_eq!(None, parse_line("").unwrap());
+	
+	if let Error::NoValue = parse_line("I'm a strong, independent key and ain't no value gonna mess with me.").unwrap_err() {} else {panic!()};
+}
+
+#[test]
+fn test_format() {
+	use text::style::{Color, PaletteColor, StyleFlags};
+	
+	let gray = Style { color: Color::Palette(PaletteColor::Gray), flags: StyleFlags::none() };
+	let compiled = Compiled::compile("Gave %2$s * %3$S to %1$s").unwrap();
+	
+	let name = "§cSteve".parse::<PlainBuf>().unwrap();
+	let red = name.iter().next().unwrap().1;
+	let item = serde_json::from_str::<Value>(r#"{"text":"stone","bold":true}"#).unwrap();
+	let count = serde_json::from_str::<Value>("3").unwrap();
+	
+	let formatted = compiled.format(gray, &[Arg::Plain(&name), Arg::Json(&count), Arg::Json(&item)]).unwrap();
+	assert_eq!(formatted.iter().collect::<Vec<_>>(), vec![
+		("Gave 3 * ", gray), 
+		("STONE", Style { color: Color::Palette(PaletteColor::Gray), flags: StyleFlags::none().set_bold(true) }), 
+		(" to ", gray), 
+		("Steve", red)
+	]);
+	
+	let formatted = Compiled::compile("%s%%%n").unwrap().format(Style::new(), &[Arg::Str("100")]).unwrap();
+	assert_eq!(formatted.unstyled(), "100%\n");
+	
+	let compiled = Compiled::compile("%-6s|%5.2f|%,d|%2$08.1f|%-3%|%.3S").unwrap();
+	let pi = serde_json::from_str::<Value>("3.14159").unwrap();
+	let formatted = compiled.format(Style::new(), &[Arg::Str("ab"), Arg::Json(&pi), Arg::Str("1234567"), Arg::Str("stone")]).unwrap();
+	assert_eq!(formatted.unstyled(), "ab    | 3.14|1,234,567|000003.1|%  |STO");
+	
+	// Arguments that a numeric conversion doesn't accept are inserted as text instead.
+	let formatted = Compiled::compile("%d and %5d").unwrap().format(Style::new(), &[Arg::Str("many"), Arg::Str("few")]).unwrap();
+	assert_eq!(formatted.unstyled(), "many and   few");
+	
+	match compiled.format(gray, &[Arg::Str("a")]) {
+		Err(FormatError::MissingArgument(1)) => (),
+		other => panic!("expected a missing argument, got {:?}", other)
+	}
+	
+	match Compiled::compile("%s").unwrap().format(gray, &[Arg::Json(&Value::Null)]) {
+		Err(FormatError::Null) => (),
+		other => panic!("expected a null argument to be rejected, got {:?}", other)
+	}
+	
+	// Nested translations are written as their key, and interactions are dropped.
+	let nested = serde_json::from_str::<Value>(r#"{"translate":"menu.quit","clickEvent":{"action":"open_url","value":"https://example.com"}}"#).unwrap();
+	let formatted = Compiled::compile("[%s]").unwrap().format(gray, &[Arg::Json(&nested)]).unwrap();
+	assert_eq!(formatted.iter().collect::<Vec<_>>(), vec![("[menu.quit]", gray)]);
+}
+
+#[test]
+fn test_save() {
+	let source = "menu.quit=Quit Game\nchat.type.text=<%s> %s\ncommands.tp.success=Teleported %2$s to %1$-8S, %<s\ngui.progress=%,d of %05.1f%% done%n\n# A comment\ngui.broken=%q\nmenu=Menu\n";
+	let dir = Directory::load(&mut source.as_bytes(), "en_US.lang").unwrap();
+	
+	let mut saved = Vec::new();
+	dir.save(&mut saved).unwrap();
+	let saved = String::from_utf8(saved).unwrap();
+	
+	assert_eq!(saved, "chat.type.text=<%s> %s\ncommands.tp.success=Teleported %2$s to %1$-8S, %<s\ngui.broken=%q\ngui.progress=%,d of %05.1f%% done%n\nmenu=Menu\nmenu.quit=Quit Game\n");
+	assert_eq!(Directory::load(&mut saved.as_bytes(), "en_US.lang").unwrap(), dir);
+	
+	let skipped = load_lang("a=A\nno value here\nb=B\n", "en_US.lang").unwrap();
+	assert_eq!(skipped.entries().len(), 2);
+}
+
+#[test]
+fn test_load_json() {
+	let source = "{\n\t\"menu.quit\": \"Quit Game\",\n\t\"chat.type.text\": \"<%s> %s\",\n\t\"gui.caf\\u00e9\": \"\\u00c9t\\u00e9 \\\"%,d\\\" \\ud83d\\ude00\",\n\t\"gui.broken\": \"\\u00e9%q\"\n}\n";
+	let lang = "menu.quit=Quit Game\nchat.type.text=<%s> %s\ngui.café=Été \"%,d\" 😀\n";
+	
+	assert_eq!(Format::detect("en_us.json", "menu.quit=Quit Game"), Format::Json);
+	assert_eq!(Format::detect("en_US.lang", source), Format::Lang);
+	assert_eq!(Format::detect("pack", source), Format::Json);
+	assert_eq!(Format::detect("pack", lang), Format::Lang);
+	
+	let json = Directory::load(&mut source.as_bytes(), "en_us.json").unwrap();
+	let lang = Directory::load(&mut lang.as_bytes(), "en_US.lang").unwrap();
+	
+	for key in &["menu.quit", "chat.type.text", "gui.café"] {
+		assert_eq!(json.get(key), lang.get(key));
+	}
+	
+	// The error points to the '%' after the escaped 'é' on the fifth line.
+	let err = json.get("gui.broken").unwrap().as_ref().unwrap_err();
+	assert_eq!((err.line, err.index, &err.text[..]), (4, 22, "\t\"gui.broken\": \"\\u00e9%q\""));
+	
+	match load_json("{\"a\": \"b\" \"c\": \"d\"}", "en_us.json") {
+		Err(Error::Syntax { line: 0, column: 10, .. }) => (),
+		other => panic!("expected a syntax error, got {:?}", other)
+	}
+	
+	match load_json("{\"a\": 1}", "en_us.json") {
+		Err(Error::Syntax { line: 0, column: 6, expected: "a string value" }) => (),
+		other => panic!("expected a syntax error, got {:?}", other)
+	}
+}
+
+#[test]
+fn test_load_escapes() {
+	let source = "#PARSE_ESCAPES\n! A comment\ngui.motd = Line one\\nLine two \\\n    continued\ngui.path:C\\:\\\\Games\\tquit\ngui.spaced\\ key \\u00e9%s\r\ngui.emoji=\\ud83d\\ude00 %d\ngui.bad=ok \\u00zz\ngui.format=\\u0041%q\n";
+	let dir = Directory::load(&mut source.as_bytes(), "en_US.lang").unwrap();
+	
+	let get = |key: &str| dir.get(key).unwrap().as_ref().unwrap().source();
+	
+	assert_eq!(get("gui.motd"), "Line one%nLine two continued");
+	assert_eq!(get("gui.path"), "C:\\Games\tquit");
+	assert_eq!(get("gui.spaced key"), "é%s");
+	assert_eq!(get("gui.emoji"), "😀 %d");
+	
+	// Errors point to the escape or format code in the line where it was written.
+	let err = dir.get("gui.bad").unwrap().as_ref().unwrap_err();
+	assert_eq!((&err.err, err.line, err.index), (&ProcessError::MalformedEscape, 7, 11));
+	
+	let err = dir.get("gui.format").unwrap().as_ref().unwrap_err();
+	assert_eq!((err.line, err.index, &err.text[..]), (8, 17, "gui.format=\\u0041%q"));
+	
+	// Without the header, lines are split at the first '=' and backslashes are kept.
+	let plain = Directory::load(&mut "gui.path=C:\\Games\\n=x\n".as_bytes(), "en_US.lang").unwrap();
+	assert_eq!(plain.get("gui.path").unwrap().as_ref().unwrap().source(), "C:\\Games\\n=x");
+	
+	// Saving switches to the escaped format when an entry can't be written plainly, and loading it back gives the same entries.
+	let mut saved = Vec::new();
+	dir.save(&mut saved).unwrap();
+	let saved = String::from_utf8(saved).unwrap();
+	
+	assert!(saved.starts_with("#PARSE_ESCAPES\n"));
+	assert_eq!(Directory::load(&mut saved.as_bytes(), "en_US.lang").unwrap(), dir);
+}
+
+#[cfg(test)]
+struct MemorySource(Vec<(&'static str, &'static str)>);
+
+#[cfg(test)]
+impl AssetSource<Directory> for MemorySource {
+	type Ticket = &'static [u8];
+	
+	fn lookup(&self, asset: &str) -> Option<Self::Ticket> {
+		self.0.iter().find(|&&(name, _)| name == asset).map(|&(_, source)| source.as_bytes())
+	}
+	
+	fn load(&self, ticket: &mut Self::Ticket, name: &str) -> Result<Directory, Error> {
+		Directory::load(ticket, name)
+	}
+}
+
+#[test]
+fn test_language_stack() {
+	let source = MemorySource(vec![
+		("lang/en_us.json", "{\"menu.quit\": \"Quit Game\", \"menu.options\": \"Options...\", \"menu.broken\": \"%s\"}"),
+		("lang/de_de.lang", "menu.quit=Spiel beenden\nmenu.broken=%q\n")
+	]);
+	
+	let stack = LanguageStack::load(&source, "de_DE").unwrap();
+	assert_eq!(stack.languages().len(), 2);
+	
+	assert_eq!(stack.translate("menu.quit").source(), "Spiel beenden");
+	assert_eq!(stack.translate("menu.options").source(), "Options...");
+	// Translations that failed to load fall back as if they were missing.
+	assert_eq!(stack.translate("menu.broken").source(), "%s");
+	assert_eq!(stack.translate("menu.missing").source(), "menu.missing");
+	assert_eq!(stack.translate("100%").source(), "100%%");
+	assert!(stack.get("menu.missing").is_none());
+	
+	// Locales without a language file only use the fallback.
+	let stack = LanguageStack::load(&source, "xx_XX").unwrap();
+	assert_eq!(stack.languages().len(), 1);
+	assert_eq!(stack.translate("menu.quit").source(), "Quit Game");
+}
+
+#[test]
+fn test_lint() {
+	let source = "menu.quit=Quit\nno value here\n key =x\nmenu.quit=Quit Game\nbad=%y\nmixed=%s %d %1$s\ntypes=%1$d %1$f\n";
+	let diagnostics = lint(source, "lang/xx_XX.lang");
+	
+	let summary = diagnostics.iter().map(|d| (d.level, d.line, d.index)).collect::<Vec<_>>();
+	assert_eq!(summary, vec![
+		(Level::Error, 1, 13),
+		(Level::Warning, 2, 0),
+		(Level::Warning, 3, 0),
+		(Level::Error, 4, 4),
+		(Level::Warning, 5, 12),
+		(Level::Warning, 6, 11)
+	]);
+	
+	assert_eq!(diagnostics[2].message, "duplicate key \"menu.quit\", replacing the translation on line 1");
+	assert!(diagnostics.iter().all(|d| d.file == "lang/xx_XX.lang"));
+	assert!(diagnostics[3].to_string().starts_with("error: "));
+	assert!(diagnostics[3].to_string().contains("  --> lang/xx_XX.lang:5:5"));
+	
+	assert_eq!(lint("{\"a\": 1}", "lang/xx_XX.json").len(), 1);
+	assert!(lint("a=%s\nb=%1$s %1$s\n", "lang/xx_XX.lang").is_empty());
+	assert!(lint("a=%1$s %1$d\nb=%1$d %1$x %1$c\nc=%1$e %1$f\n", "lang/xx_XX.lang").is_empty());
+	assert_eq!(lint("a=%1$c %1$f\nb=%1$S %1$x %1$e\n", "lang/xx_XX.lang").len(), 2);
+}
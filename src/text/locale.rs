@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Formatter, Display};
+use std::io::{Read, Write};
+use resource::Asset;
+use serde_json;
+
+/// Language codes longer than this are rejected, like the vanilla client does.
+pub const MAX_CODE_LEN: usize = 16;
+
+/// A language offered by a resource pack, as described in the `language` section of its `pack.mcmeta` file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageInfo {
+	/// The name of the language in the language itself, such as "Deutsch".
+	pub name: String,
+	/// The region where this variant of the language is spoken, such as "Deutschland".
+	pub region: String,
+	/// Whether the language is written from right to left.
+	#[serde(default)]
+	pub bidirectional: bool
+}
+
+/// Formats the language like the language selection screen does, such as "English (US)".
+impl Display for LanguageInfo {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		write!(f, "{} ({})", self.name, self.region)
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackMeta {
+	#[serde(default)]
+	language: BTreeMap<String, LanguageInfo>
+}
+
+#[derive(Debug)]
+pub enum MetaError {
+	Json(serde_json::Error),
+	CodeTooLong(String)
+}
+
+impl Display for MetaError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			MetaError::Json(ref err) => write!(f, "invalid pack.mcmeta: {}", err),
+			MetaError::CodeTooLong(ref code) => write!(f, "language code {:?} is longer than {} characters", code, MAX_CODE_LEN)
+		}
+	}
+}
+
+/// The languages that are available from a set of resource packs, by language code such as `en_US`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locales {
+	languages: BTreeMap<String, LanguageInfo>
+}
+
+impl Locales {
+	pub fn new() -> Self {
+		Locales { languages: BTreeMap::new() }
+	}
+	
+	/// Adds the languages from the `pack.mcmeta` file of a resource pack. Packs added later replace the languages of earlier packs with the same code.
+	pub fn add_pack<R>(&mut self, read: &mut R) -> Result<(), MetaError> where R: Read {
+		let meta: PackMeta = serde_json::from_reader(read).map_err(MetaError::Json)?;
+		
+		if let Some(code) = meta.language.keys().find(|code| code.len() > MAX_CODE_LEN) {
+			return Err(MetaError::CodeTooLong(code.clone()));
+		}
+		
+		self.languages.extend(meta.language);
+		
+		Ok(())
+	}
+	
+	/// Returns the language with the code. Codes are compared without regard to case, as the files were renamed from `en_US` to `en_us` in 1.11.
+	pub fn get(&self, code: &str) -> Option<&LanguageInfo> {
+		self.languages.get(code).or_else(|| self.iter().find(|&(other, _)| other.eq_ignore_ascii_case(code)).map(|(_, info)| info))
+	}
+	
+	/// Iterates over the codes and languages, sorted by code.
+	pub fn iter(&self) -> ::std::collections::btree_map::Iter<String, LanguageInfo> {
+		self.languages.iter()
+	}
+	
+	pub fn len(&self) -> usize {
+		self.languages.len()
+	}
+}
+
+/// Loads the languages of a single `pack.mcmeta` file, or saves them as the `language` section of one.
+impl Asset for Locales {
+	type Err = MetaError;
+	type WErr = serde_json::Error;
+	
+	fn load<R>(read: &mut R, _: &str) -> Result<Self, Self::Err> where R: Read {
+		let mut locales = Locales::new();
+		locales.add_pack(read)?;
+		
+		Ok(locales)
+	}
+	
+	fn save<W>(&self, write: &mut W) -> Result<(), Self::WErr> where W: Write {
+		serde_json::to_writer_pretty(write, &PackMeta { language: self.languages.clone() })
+	}
+}
+
+/// How numbers are written in a locale: the separators used by the ',' flag and for the decimal point of format codes such as %f.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct NumberFormat {
+	/// Separates groups of digits in the integer part, such as the '.' in the German "1.234".
+	pub grouping: char,
+	/// Separates the integer part from the fractional part, such as the ',' in the German "0,5".
+	pub decimal: char,
+	/// The amount of digits in each group.
+	pub grouping_size: usize
+}
+
+/// The locales that write numbers differently from en_US, from the locale data of Java 8.
+/// Java uses a no-break space to group digits in many locales, so a number is never split across lines.
+static NUMBER_FORMATS: &'static [(&'static str, char, char, usize)] = &[
+	("be_BY", '\u{A0}', ',', 3),
+	("bg_BG", '\u{A0}', ',', 3),
+	("ca_ES", '.', ',', 3),
+	("cs_CZ", '\u{A0}', ',', 3),
+	("da_DK", '.', ',', 3),
+	("de_AT", '.', ',', 3),
+	("de_CH", '\'', '.', 3),
+	("de_DE", '.', ',', 3),
+	("el_GR", '.', ',', 3),
+	("es_AR", '.', ',', 3),
+	("es_CL", '.', ',', 3),
+	("es_ES", '.', ',', 3),
+	("es_UY", '.', ',', 3),
+	("es_VE", '.', ',', 3),
+	("et_EE", '\u{A0}', ',', 3),
+	("fi_FI", '\u{A0}', ',', 3),
+	("fr_CA", '\u{A0}', ',', 3),
+	("fr_FR", '\u{A0}', ',', 3),
+	("hr_HR", '.', ',', 3),
+	("hu_HU", '\u{A0}', ',', 3),
+	("id_ID", '.', ',', 3),
+	("is_IS", '.', ',', 3),
+	("it_IT", '.', ',', 3),
+	("lt_LT", '\u{A0}', ',', 3),
+	("lv_LV", '\u{A0}', ',', 3),
+	("mk_MK", '.', ',', 3),
+	("nl_NL", '.', ',', 3),
+	("nn_NO", '\u{A0}', ',', 3),
+	("no_NO", '\u{A0}', ',', 3),
+	("pl_PL", '\u{A0}', ',', 3),
+	("pt_BR", '.', ',', 3),
+	("pt_PT", '.', ',', 3),
+	("ro_RO", '.', ',', 3),
+	("ru_RU", '\u{A0}', ',', 3),
+	("sk_SK", '\u{A0}', ',', 3),
+	("sl_SI", '.', ',', 3),
+	("sq_AL", '.', ',', 3),
+	("sr_SP", '.', ',', 3),
+	("sv_SE", '\u{A0}', ',', 3),
+	("tr_TR", '.', ',', 3),
+	("uk_UA", '\u{A0}', ',', 3),
+	("vi_VN", '.', ',', 3)
+];
+
+impl NumberFormat {
+	/// Returns how numbers are written in the locale, such as `de_DE`. Codes are compared without regard to case, and unknown locales write numbers like en_US.
+	pub fn for_locale(code: &str) -> Self {
+		NUMBER_FORMATS.iter()
+			.find(|&&(other, _, _, _)| other.eq_ignore_ascii_case(code))
+			.map(|&(_, grouping, decimal, grouping_size)| NumberFormat { grouping: grouping, decimal: decimal, grouping_size: grouping_size })
+			.unwrap_or(NumberFormat::en_us())
+	}
+	
+	pub fn en_us() -> Self {
+		NumberFormat { grouping: ',', decimal: '.', grouping_size: 3 }
+	}
+}
+
+#[test]
+fn test_locales() {
+	let vanilla = r#"{"pack":{"pack_format":3,"description":"Default"},"language":{
+		"en_us":{"name":"English","region":"US","bidirectional":false},
+		"ar_sa":{"name":"اللغة العربية","region":"العالم العربي","bidirectional":true},
+		"de_de":{"name":"Deutsch","region":"Deutschland"}
+	}}"#;
+	let custom = r#"{"pack":{"pack_format":3,"description":"Pirate"},"language":{"en_pt":{"name":"Pirate Speak","region":"The Seven Seas"},"de_de":{"name":"Deutsch","region":"Österreich"}}}"#;
+	
+	let mut locales = Locales::load(&mut vanilla.as_bytes(), "pack.mcmeta").unwrap();
+	locales.add_pack(&mut custom.as_bytes()).unwrap();
+	locales.add_pack(&mut r#"{"pack":{"pack_format":3,"description":"No languages"}}"#.as_bytes()).unwrap();
+	
+	assert_eq!(locales.iter().map(|(code, _)| &code[..]).collect::<Vec<_>>(), vec!["ar_sa", "de_de", "en_pt", "en_us"]);
+	assert!(locales.get("ar_SA").unwrap().bidirectional);
+	assert_eq!(locales.get("en_US").unwrap().to_string(), "English (US)");
+	assert_eq!(locales.get("de_de").unwrap().region, "Österreich");
+	
+	let mut saved = Vec::new();
+	locales.save(&mut saved).unwrap();
+	assert_eq!(Locales::load(&mut &saved[..], "pack.mcmeta").unwrap(), locales);
+	
+	match locales.add_pack(&mut r#"{"language":{"this_is_way_too_long":{"name":"?","region":"?"}}}"#.as_bytes()) {
+		Err(MetaError::CodeTooLong(_)) => (),
+		other => panic!("expected a long code to be rejected, got {:?}", other)
+	}
+	
+	match locales.add_pack(&mut r#"{"language":{"xx_xx":{"name":"?"}}}"#.as_bytes()) {
+		Err(MetaError::Json(_)) => (),
+		other => panic!("expected a missing region to be rejected, got {:?}", other)
+	}
+}
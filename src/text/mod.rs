@@ -4,6 +4,10 @@ pub mod default;
 pub mod align;
 pub mod render;
 pub mod language;
+pub mod locale;
+pub mod consistency;
+pub mod pseudo;
 mod formatter;
 pub mod pages;
-pub mod repr;
\ No newline at end of file
+pub mod repr;
+pub mod process;
\ No newline at end of file
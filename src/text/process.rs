@@ -1,36 +1,285 @@
-use language::Directory;
+use text::language::{self, Directory, LanguageStack, Compiled, Part};
+use text::repr::flat::{ChatBuf, Component, Kind, Mode, Interaction, HoverEvent, MAX_INDUVIDUAL_LEN};
+use text::style::Style;
+use std::borrow::Cow;
+use std::ops::Range;
 
-#[derive(Copy, Clone)]
-enum State {
-	Main,
-	Translating(usize),
-	End
+/// Resolves the translations in chat components with a language. Each translation is replaced by a text component holding the 
+/// translated text up to the first argument, followed by children holding the rest of the text and the arguments. 
+/// The arguments keep their own style, which already inherits the style of the translation.
+pub struct Processor<'l> {
+	lang: &'l [Directory]
 }
 
-struct Processor<'a, I> where I: Iterator<Item=Component<'a>> {
-	lang: Option<Directory>,
-	source: I,
-	state: Vec<State>
+impl<'l> Processor<'l> {
+	pub fn new(lang: &'l Directory) -> Self {
+		Processor { lang: ::std::slice::from_ref(lang) }
+	}
+	
+	/// Creates a processor that looks up each translation in the languages of the stack in order.
+	pub fn with_stack(stack: &'l LanguageStack) -> Self {
+		Processor { lang: stack.languages() }
+	}
+	
+	/// Creates a processor without a language, where every translation is missing.
+	pub fn without_language() -> Self {
+		Processor { lang: &[] }
+	}
+	
+	/// Returns a copy of the components with every translation resolved, including the translations in the arguments and in the text of hover events.
+	/// Missing translations are shown as their key, like the vanilla client does. The key is still formatted, but arguments that are not referenced are dropped.
+	pub fn process(&self, source: &ChatBuf) -> ChatBuf {
+		let mut level = 0;
+		
+		let nodes = source.components().map(|component| {
+			match component.mode() {
+				Mode::Level => (),
+				Mode::Deeper => level += 1,
+				Mode::Shallower(reduction) => level -= ::std::cmp::min(level, reduction as usize)
+			};
+			
+			(level, component)
+		}).collect::<Vec<_>>();
+		
+		let mut expansion = Expansion {
+			lang: self.lang,
+			source: source,
+			nodes: nodes,
+			target: ChatBuf::with_capacity(source.len(), 0),
+			level: 0,
+			len: 0,
+			spans: Vec::new()
+		};
+		
+		let mut index = 0;
+		
+		while index < expansion.nodes.len() {
+			index = expansion.subtree(index, 0, false, false);
+		}
+		
+		expansion.finish()
+	}
+	
+	fn translation(&self, key: &str) -> Option<Cow<'l, Compiled>> {
+		match language::lookup(self.lang, key) {
+			Some(compiled) => Some(Cow::Borrowed(compiled)),
+			None => Compiled::compile(key).ok().map(Cow::Owned)
+		}
+	}
 }
 
-/*impl<'a, I> Iterator for Processor<'a, I> where I: Iterator<Item=Component<'a>> {
-	type Item = Result<Component<'a>, ComponentBuf>;
-	
-	fn next(&mut self) -> Option<Self::Item> {
-		Some(match self.state {
-			State::Main => {
-				match self.source.next() {
-					Some(component) => {
-						match component.kind {
-							Kind::Text => Ok(component),
-							Kind::Translate => 
+/// The state of a single call to `Processor::process`.
+struct Expansion<'l, 'a> {
+	lang: &'l [Directory],
+	source: &'a ChatBuf,
+	/// The components of the source along with their levels.
+	nodes: Vec<(usize, Component<'a>)>,
+	target: ChatBuf,
+	level: usize,
+	len: usize,
+	spans: Vec<(Range<usize>, Interaction)>
+}
+
+impl<'l, 'a> Expansion<'l, 'a> {
+	/// Writes the component at `index` and its children at `level`, returning the index of the next component that is not one of its children.
+	/// If `upper` is set, text is converted to uppercase, which is used for arguments referenced by `%S`.
+	fn subtree(&mut self, index: usize, level: usize, meta: bool, upper: bool) -> usize {
+		let component = self.nodes[index].1;
+		let end = self.skip(index);
+		let span = self.open(index);
+		
+		if component.kind() == Kind::Translate {
+			let (mut args, mut extra) = (Vec::new(), Vec::new());
+			let mut child = index + 1;
+			
+			while child < end {
+				if self.nodes[child].1.meta() { args.push(child) } else { extra.push(child) }
+				
+				child = self.skip(child);
+			}
+			
+			let processor = Processor { lang: self.lang };
+			
+			let translation = processor.translation(component.text());
+			
+			// Like the vanilla client, a translation that refers to an argument past the end of `with` is shown as its raw translation string.
+			let missing = translation.as_ref().map(|compiled| compiled.parts().any(|part| match part {
+				Part::Arg { index, .. } => index >= args.len(),
+				Part::Text(_) => false
+			})).unwrap_or(false);
+			
+			if missing {
+				let source = translation.map(|compiled| compiled.source()).unwrap_or_default();
+				self.push(&source, level, meta, Kind::Text, component.style(), upper);
+			} else if let Some(compiled) = translation {
+				let mut parts = compiled.parts().peekable();
+				
+				let head = match parts.peek() {
+					Some(&Part::Text(text)) => text,
+					_ => ""
+				};
+				
+				if !head.is_empty() {
+					parts.next();
+				}
+				
+				self.push(head, level, meta, Kind::Text, component.style(), upper);
+				
+				for part in parts {
+					match part {
+						Part::Text(text) => self.push(text, level + 1, false, Kind::Text, component.style(), upper),
+						Part::Arg { index: arg, upper: arg_upper, .. } => {
+							self.subtree(args[arg], level + 1, false, upper || arg_upper);
 						}
-					},
-					None => {self.state = State::End; return None}
+					}
 				}
-			},
-			State::Translating => unimplemented!(),
-			State::End => return None
+			} else {
+				self.push(component.text(), level, meta, Kind::Text, component.style(), upper);
+			}
+			
+			for child in extra {
+				self.subtree(child, level + 1, false, upper);
+			}
+		} else {
+			self.push(component.text(), level, meta, component.kind(), component.style(), upper && component.kind() == Kind::Text);
+			
+			let mut child = index + 1;
+			
+			while child < end {
+				let meta = self.nodes[child].1.meta();
+				child = self.subtree(child, level + 1, meta, upper);
+			}
+		}
+		
+		if let Some(span) = span {
+			self.spans[span].0.end = self.len;
+		}
+		
+		end
+	}
+	
+	/// Returns the index of the next component after `index` that is not one of its children.
+	fn skip(&self, index: usize) -> usize {
+		let level = self.nodes[index].0;
+		let mut end = index + 1;
+		
+		while end < self.nodes.len() && self.nodes[end].0 > level {
+			end += 1;
+		}
+		
+		end
+	}
+	
+	/// Starts the span of the interaction of the component at `index`, if it has one. The text of `show_text` events is processed as well.
+	fn open(&mut self, index: usize) -> Option<usize> {
+		self.source.own_interaction(index).map(|interaction| {
+			let hover = interaction.hover.as_ref().map(|hover| match *hover {
+				HoverEvent::ShowText(ref text) => HoverEvent::ShowText(Processor { lang: self.lang }.process(text)),
+				ref other => other.clone()
+			});
+			
+			let interaction = Interaction {
+				insert: interaction.insert.clone(),
+				click: interaction.click.clone(),
+				hover: hover
+			};
+			
+			self.spans.push((self.len..self.len, interaction));
+			self.spans.len() - 1
 		})
 	}
-}*/
\ No newline at end of file
+	
+	fn push(&mut self, text: &str, level: usize, meta: bool, kind: Kind, style: Style, upper: bool) {
+		let text = if upper { Cow::Owned(text.to_uppercase()) } else { Cow::Borrowed(text) };
+		let mut rest = &text as &str;
+		
+		// Converting to uppercase may make the text too long for one component.
+		loop {
+			let mut len = ::std::cmp::min(rest.len(), MAX_INDUVIDUAL_LEN);
+			
+			while !rest.is_char_boundary(len) {
+				len -= 1;
+			}
+			
+			let (part, next) = rest.split_at(len);
+			rest = next;
+			
+			let mode = if level > self.level {
+				Mode::Deeper
+			} else if level == self.level {
+				Mode::Level
+			} else {
+				Mode::Shallower((self.level - level) as u8)
+			};
+			
+			self.target.push(Component::new(part, kind, style, mode, meta).unwrap());
+			self.level = level;
+			self.len += 1;
+			
+			if rest.is_empty() {
+				break;
+			}
+		}
+	}
+	
+	fn finish(self) -> ChatBuf {
+		let Expansion { mut target, spans, .. } = self;
+		
+		for (range, interaction) in spans {
+			target.interact(range, interaction);
+		}
+		
+		target
+	}
+}
+
+#[cfg(test)]
+fn processed(lang: &Processor, json: &str) -> Vec<(String, Style, Mode)> {
+	lang.process(&ChatBuf::from_json(json).unwrap()).components().map(|c| (c.text().to_owned(), c.style(), c.mode())).collect()
+}
+
+#[test]
+fn test_process() {
+	use resource::Asset;
+	use text::style::{Color, PaletteColor, StyleFlags};
+	
+	let source = "chat.type.text=<%s> %s\ncommands.give.success=Gave %2$s * %3$s to %1$s\ntest.upper=%S!\n";
+	let lang = Directory::load(&mut source.as_bytes(), "test.lang").unwrap();
+	let processor = Processor::new(&lang);
+	
+	let gray = Style { color: Color::Palette(PaletteColor::Gray), flags: StyleFlags::none() };
+	let red = Style { color: Color::Palette(PaletteColor::Red), flags: StyleFlags::none() };
+	
+	assert_eq!(processed(&processor, r#"{"translate":"chat.type.text","with":[{"text":"Steve","color":"red"},"hi"],"color":"gray","extra":["!"]}"#), vec![
+		("<".to_owned(), gray, Mode::Level),
+		("Steve".to_owned(), red, Mode::Deeper),
+		("> ".to_owned(), gray, Mode::Level),
+		("hi".to_owned(), gray, Mode::Level),
+		("!".to_owned(), gray, Mode::Level)
+	]);
+	
+	let text = |processor: &Processor, json: &str| processor.process(&ChatBuf::from_json(json).unwrap()).to_plain().value.unstyled().to_owned();
+	
+	assert_eq!(text(&processor, r#"{"translate":"commands.give.success","with":["Alex","Stone",3]}"#), "Gave Stone * 3 to Alex");
+	assert_eq!(text(&processor, r#"{"translate":"test.upper","with":[{"translate":"chat.type.text","with":["a",{"text":"b","extra":["c"]}]}]}"#), "<A> BC!");
+	
+	// Missing translations are shown as their key.
+	assert_eq!(text(&processor, r#"{"translate":"no.such.key","with":["x"]}"#), "no.such.key");
+	assert_eq!(text(&Processor::without_language(), r#"{"translate":"chat.type.text","with":["x"]}"#), "chat.type.text");
+	
+	// Translations with too few arguments are shown as their raw translation string.
+	assert_eq!(text(&processor, r#"{"translate":"chat.type.text","with":["x"],"extra":["!"]}"#), "<%s> %s!");
+	assert_eq!(text(&processor, r#"{"translate":"commands.give.success","with":["Alex","Stone"]}"#), "Gave %2$s * %3$s to %1$s");
+	
+	// Interactions of the translation and the arguments are kept.
+	let buf = processor.process(&ChatBuf::from_json(r#"{"translate":"chat.type.text","insertion":"t","with":[{"text":"Steve","insertion":"s","hoverEvent":{"action":"show_text","value":{"translate":"test.upper","with":["tip"]}}},"hi"]}"#).unwrap());
+	
+	assert_eq!(buf.interaction_at_byte(0).unwrap().insert, Some("t"));
+	assert_eq!(buf.interaction_at_byte(1).unwrap().insert, Some("s"));
+	assert_eq!(buf.interaction_at_byte(8).unwrap().insert, Some("t"));
+	
+	match buf.interaction_at_byte(1).unwrap().hover {
+		Some(&HoverEvent::ShowText(ref text)) => assert_eq!(text.to_plain().value.unstyled(), "TIP!"),
+		ref other => panic!("expected show_text, got {:?}", other)
+	}
+}
@@ -0,0 +1,148 @@
+use text::language::{Directory, Compiled, Part};
+
+/// Generates a pseudo-locale from a language, for finding text that does not fit in the user interface before it is translated.
+/// Letters are replaced with accented look-alikes from `ascii.png`, so the text stays readable and is still rendered with the default font.
+/// Format codes and § formatting codes are kept as they are.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PseudoLocale {
+	/// How much longer each translation becomes, in percent of the length of its text. Translations into languages such as German are often 30% longer than English.
+	pub expansion: usize,
+	/// Whether to surround each translation with brackets, which makes text that is cut off easy to spot.
+	pub brackets: bool
+}
+
+impl PseudoLocale {
+	pub fn new(expansion: usize) -> Self {
+		PseudoLocale { expansion: expansion, brackets: true }
+	}
+	
+	/// Creates the pseudo-locale of every translation in the language. Translations that failed to load are copied as they are.
+	pub fn transform(&self, language: &Directory) -> Directory {
+		let mut pseudo = Directory::new();
+		
+		for (key, translation) in language.entries() {
+			pseudo.insert(&key, match *translation {
+				Ok(ref compiled) => Ok(self.translate(compiled)),
+				Err(ref err) => Err(err.clone())
+			});
+		}
+		
+		pseudo
+	}
+	
+	/// Creates the pseudo-locale of a single translation. Empty translations stay empty.
+	pub fn translate(&self, compiled: &Compiled) -> Compiled {
+		if compiled.parts().next().is_none() {
+			return compiled.clone();
+		}
+		
+		let args = compiled.parts().filter(|part| match *part { Part::Arg { .. } => true, _ => false }).count();
+		
+		// Only the visible characters count towards the length, not the formatting codes.
+		let length = compiled.parts().map(|part| match part {
+			Part::Text(text) => visible_chars(text),
+			Part::Arg { .. } => 0
+		}).sum::<usize>();
+		
+		let padding = (length * self.expansion + 99) / 100;
+		
+		// A formatting code may be split from its character by an argument, such as in "§%s".
+		let mut code = false;
+		
+		compiled.map_text(|run, text| {
+			let mut mapped = String::with_capacity(text.len() * 2);
+			
+			if run == 0 && self.brackets {
+				mapped.push('[');
+			}
+			
+			for c in text.chars() {
+				if code {
+					mapped.push(c);
+					code = false;
+				} else if c == '\u{A7}' {
+					mapped.push(c);
+					code = true;
+				} else {
+					mapped.push(accent(c));
+				}
+			}
+			
+			if run == args {
+				if padding > 0 {
+					mapped.push(' ');
+				}
+				
+				for _ in 1..padding {
+					mapped.push('~');
+				}
+				
+				if self.brackets {
+					mapped.push(']');
+				}
+			}
+			
+			mapped
+		})
+	}
+}
+
+/// Counts the characters of the text, excluding § formatting codes.
+fn visible_chars(text: &str) -> usize {
+	let mut count = 0;
+	let mut code = false;
+	
+	for c in text.chars() {
+		if code {
+			code = false;
+		} else if c == '\u{A7}' {
+			code = true;
+		} else {
+			count += 1;
+		}
+	}
+	
+	count
+}
+
+/// Returns an accented look-alike of the letter, or the character itself if `ascii.png` has none.
+pub fn accent(c: char) -> char {
+	match c {
+		'A' => '\u{C5}', 'a' => '\u{E5}',
+		'C' => '\u{C7}', 'c' => '\u{E7}',
+		'E' => '\u{C9}', 'e' => '\u{E9}',
+		'I' => '\u{130}', 'i' => '\u{EF}',
+		'N' => '\u{D1}', 'n' => '\u{F1}',
+		'O' => '\u{D6}', 'o' => '\u{F6}',
+		'S' => '\u{15E}', 's' => '\u{15F}',
+		'U' => '\u{DC}', 'u' => '\u{FC}',
+		'W' => '\u{174}', 'w' => '\u{175}',
+		'g' => '\u{11F}',
+		'y' => '\u{FF}',
+		'z' => '\u{17E}',
+		c => c
+	}
+}
+
+#[test]
+fn test_pseudo() {
+	use text::default::character_to_default;
+	use text::language;
+	
+	for c in (b'A'..b'Z' + 1).chain(b'a'..b'z' + 1).map(|c| c as char) {
+		assert!(character_to_default(accent(c)).is_some(), "{:?} is replaced with {:?}, which is not in ascii.png", c, accent(c));
+	}
+	
+	let language = language::load_lang("a=Hello %s!\nb=\u{A7}eGame %1$s\u{A7}r\nc=\nd=100%% done\n", "en_US.lang").unwrap();
+	let pseudo = PseudoLocale::new(40).transform(&language);
+	
+	let source = |key| pseudo.get(key).unwrap().as_ref().unwrap().source();
+	
+	assert_eq!(source("a"), "[H\u{E9}ll\u{F6} %s! ~~]");
+	assert_eq!(source("b"), "[\u{A7}eG\u{E5}m\u{E9} %1$s\u{A7}r ~]");
+	assert_eq!(source("c"), "");
+	assert_eq!(source("d"), "[100%% d\u{F6}\u{F1}\u{E9} ~~~]");
+	
+	let plain = PseudoLocale { expansion: 0, brackets: false };
+	assert_eq!(plain.translate(&Compiled::compile("Quit").unwrap()).source(), "Q\u{FC}\u{EF}t");
+}
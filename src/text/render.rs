@@ -243,14 +243,18 @@ impl<'a, I> Iterator for RenderRun<'a, I> where I: Iterator<Item=char> {
 			};
 		}
 		
-		let color = if let style::Color::Palette(pal) = self.style.color {
-			if self.shadow {
+		let color = match self.style.color {
+			style::Color::Palette(pal) => if self.shadow {
 				pal.background()
 			} else {
 				pal.foreground()
-			}
-		} else {
-			if self.shadow {
+			},
+			style::Color::Rgb(r, g, b) => if self.shadow {
+				Rgb::new(r / 4, g / 4, b / 4)
+			} else {
+				Rgb::new(r, g, b)
+			},
+			style::Color::Default => if self.shadow {
 				Rgb::new(self.color.r() / 4, self.color.g() / 4, self.color.b() / 4)
 			} else {
 				self.color
@@ -1,6 +1,18 @@
 use text::style::{Style, StyleCommand, Color, PaletteColor};
 use std::borrow::{Borrow, ToOwned};
 use std::slice::Iter;
+use std::ops::Range;
+use std::cmp;
+use serde_json;
+use text::repr::flat_serialize;
+use text::repr::flat_deserialize;
+use text::repr::lenient::{self, Reader, Leniencies};
+use text::repr::reference;
+use text::repr::plain::PlainBuf;
+
+pub use text::repr::reference::ClickEvent;
+pub type HoverEvent = reference::HoverEvent<ChatBuf>;
+pub type Interaction = reference::Interaction<ChatBuf>;
 
 pub const MAX_INDUVIDUAL_LEN: usize = 65535;
 pub const MAX_NESTING: Level = 127;
@@ -13,7 +25,7 @@ pub enum Mode {
 	Shallower(Level)
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Kind {
 	Text,
 	Translate,
@@ -29,11 +41,12 @@ pub enum Kind {
 
 // COMPACTED
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ChatBuf {
 	string: String,
 	descriptors: Vec<Descriptor>,
-	level: Level
+	level: Level,
+	interactions: Vec<Span>
 }
 
 impl ChatBuf {
@@ -41,7 +54,8 @@ impl ChatBuf {
 		ChatBuf {
 			string: String::new(),
 			descriptors: Vec::new(),
-			level: 0
+			level: 0,
+			interactions: Vec::new()
 		}
 	}
 	
@@ -49,17 +63,112 @@ impl ChatBuf {
 		ChatBuf {
 			string: String::with_capacity(string),
 			descriptors: Vec::with_capacity(descriptors),
-			level: 0
+			level: 0,
+			interactions: Vec::new()
+		}
+	}
+	
+	/// Converts a PlainBuf into a flat buffer, with each run of text as a top level component. This conversion is lossless.
+	pub fn from_plain(plain: &PlainBuf) -> Self {
+		let mut buf = ChatBuf::with_capacity(plain.unstyled().len(), 0);
+		
+		for (text, style) in plain.iter() {
+			let mut rest = text;
+			
+			// Runs that are too long for one component are split up.
+			while !rest.is_empty() {
+				let mut len = cmp::min(rest.len(), MAX_INDUVIDUAL_LEN);
+				
+				while !rest.is_char_boundary(len) {
+					len -= 1;
+				}
+				
+				let (part, next) = rest.split_at(len);
+				rest = next;
+				
+				buf.push(Component::new(part, Kind::Text, style, Mode::Level, false).unwrap());
+			}
+		}
+		
+		buf
+	}
+	
+	/// Converts the buffer into a PlainBuf with the style of each component resolved, as the vanilla client would display it.
+	/// Translations are shown as their key and keybinds as their name, like the vanilla client does when they are missing, 
+	/// and scores are shown as their value. Other parts of translations and scores are not displayed.
+	/// PlainBuf has no interactions, so they are returned separately along with the range of text that they applied to.
+	pub fn to_plain(&self) -> Lossy<PlainBuf> {
+		let mut plain = PlainBuf::with_capacity(self.string.len(), self.descriptors.len());
+		let mut offsets = Vec::with_capacity(self.descriptors.len() + 1);
+		
+		// Level of a component whose children are part of the payload of their parent, and not displayed.
+		let mut hidden: Option<Level> = None;
+		let mut head = &self.string as &str;
+		
+		for descriptor in &self.descriptors {
+			offsets.push(plain.unstyled().len());
+			
+			let (text, rest) = head.split_at(descriptor.len as usize);
+			head = rest;
+			
+			match hidden {
+				Some(level) if descriptor.level() > level => continue,
+				_ => hidden = None
+			}
+			
+			if descriptor.meta() && descriptor.kind != Kind::ScoreValue {
+				hidden = Some(descriptor.level());
+			} else if descriptor.kind != Kind::ScoreName {
+				plain.push(text, descriptor.style);
+			}
 		}
+		
+		offsets.push(plain.unstyled().len());
+		
+		let dropped = self.interactions.iter().map(|span| (offsets[span.range.start]..offsets[span.range.end], span.interaction.clone())).collect();
+		
+		Lossy { value: plain, dropped: dropped }
 	}
 	
-	// TODO: To/from PlainBuf
+	/// Parses a JSON chat component, as sent by the server, into a flat buffer.
+	/// Children from `extra` are placed one level deeper than their parent, and the arguments of translations (`with`) and the objective and value of scores are placed one level deeper with the meta flag set.
+	pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+		serde_json::from_str(json)
+	}
+	
+	/// Decodes a JSON chat component that inherits the style properties it does not set from `parent`, like the arguments of a translation do.
+	pub fn from_value_in(value: &serde_json::Value, parent: Style) -> Result<Self, serde_json::Error> {
+		flat_deserialize::deserialize_in(value, parent)
+	}
+	
+	/// Parses a JSON chat component with the same leniency as the vanilla client before 1.12, and reports the leniencies that were needed to read it.
+	/// Note that Gson reads empty array elements (`["a",]`) as null, which is not a valid component, so these are still rejected.
+	pub fn from_json_lenient(json: &str) -> Result<(Self, Leniencies), lenient::Error> {
+		let mut reader = Reader::new(json);
+		let buf = reader.document()?;
+		
+		Ok((buf, reader.leniencies()))
+	}
 	
-	// TODO: from_json[_lenient]
 	// TODO: into_formatted
-	// TODO: into_json
-	// TODO: into_json_flattened
-	// TODO: into_json_bestfit
+	
+	/// Encodes the buffer as JSON, rebuilding the original `extra` and `with` nesting from the levels of the components.
+	/// Only style properties that differ from the parent are written. Interactions are written on the component where they start, 
+	/// so interactions that cover several siblings (such as after `slice`) are only kept by the flattened and best-fit encodings.
+	pub fn into_json(&self) -> String {
+		serde_json::to_string(self).expect("serializing a ChatBuf should never fail")
+	}
+	
+	/// Encodes the buffer as JSON with one component per styled run of text. Each run has its full style specified, and does not inherit from the others.
+	/// Translations, scores, selectors, and keybinds cannot be merged into runs, so they are kept as separate components along with their arguments.
+	pub fn into_json_flattened(&self) -> String {
+		serde_json::to_string(&flat_serialize::into_flattened(self)).expect("serializing a JSON value should never fail")
+	}
+	
+	/// Encodes the buffer as the shortest JSON out of several candidate encodings that render identically, for sending over the network.
+	pub fn into_json_bestfit(&self) -> String {
+		serde_json::to_string(&flat_serialize::into_bestfit(self)).expect("serializing a JSON value should never fail")
+	}
 	
 	pub fn capacity(&self) -> (usize, usize) {
 		(self.string.capacity(), self.descriptors.capacity())
@@ -95,7 +204,219 @@ impl ChatBuf {
 		self.descriptors.push(Descriptor::new(component.text.len(), self.level, component.meta, component.kind, component.style));
 	}
 	
-	// TODO: Pop, Truncate
+	/// Removes the last component, returning its text, kind, and style.
+	pub fn pop(&mut self) -> Option<(String, Kind, Style)> {
+		let descriptor = self.descriptors.pop()?;
+		
+		let at = self.string.len() - descriptor.len as usize;
+		let text = self.string.split_off(at);
+		
+		self.trim_tail();
+		
+		Some((text, descriptor.kind, descriptor.style))
+	}
+	
+	/// Shortens the text of the buffer to `len` bytes. Does nothing if the buffer is already shorter. Panics if `len` is not on a character boundary, like `String::truncate`.
+	/// Only text components can be cut, other components such as translations are removed if they do not fit completely.
+	pub fn truncate(&mut self, len: usize) {
+		if len >= self.string.len() {
+			return;
+		}
+		
+		assert!(self.string.is_char_boundary(len), "new ChatBuf length must be on a character boundary");
+		
+		let mut start = 0;
+		let mut kept = 0;
+		
+		for descriptor in &mut self.descriptors {
+			let end = start + descriptor.len as usize;
+			
+			if end > len {
+				if start < len && descriptor.kind == Kind::Text {
+					descriptor.len = (len - start) as u16;
+					start = len;
+					kept += 1;
+				}
+				
+				break;
+			}
+			
+			start = end;
+			kept += 1;
+		}
+		
+		self.string.truncate(start);
+		self.descriptors.truncate(kept);
+		
+		self.trim_tail();
+	}
+	
+	/// Shortens the text of the buffer to `chars` characters. See `truncate`.
+	pub fn truncate_chars(&mut self, chars: usize) {
+		if let Some((len, _)) = self.string.char_indices().nth(chars) {
+			self.truncate(len);
+		}
+	}
+	
+	/// Restores the level and the interactions after descriptors were removed from the end.
+	fn trim_tail(&mut self) {
+		let len = self.descriptors.len();
+		
+		self.level = self.descriptors.last().map(|descriptor| descriptor.level()).unwrap_or(0);
+		self.interactions.retain(|span| span.range.start < len);
+		
+		for span in &mut self.interactions {
+			span.range.end = cmp::min(span.range.end, len);
+		}
+	}
+	
+	/// Copies a byte range of the text into a new buffer. Panics if the range is not on character boundaries, like slicing a `str`.
+	/// As with `truncate`, only text components can be cut. Components whose parents are not in the range are moved up to take their place, 
+	/// and lose their meta flag since they are no longer part of the payload of their parent.
+	pub fn slice(&self, range: Range<usize>) -> ChatBuf {
+		// Check the boundaries before splitting up the string.
+		let _ = &self.string[range.clone()];
+		
+		let mut kept = Vec::new();
+		let mut start = 0;
+		
+		for (index, descriptor) in self.descriptors.iter().enumerate() {
+			let end = start + descriptor.len as usize;
+			
+			if start >= range.start && end <= range.end {
+				kept.push((index, start..end));
+			} else if descriptor.kind == Kind::Text && start < range.end && end > range.start {
+				kept.push((index, cmp::max(start, range.start)..cmp::min(end, range.end)));
+			}
+			
+			start = end;
+		}
+		
+		let mut buf = ChatBuf::with_capacity(range.end - range.start, kept.len());
+		
+		// Original levels of the components that are still open, the last one being the parent of the next component.
+		let mut parents: Vec<Level> = Vec::new();
+		
+		for &(index, ref part) in &kept {
+			let descriptor = &self.descriptors[index];
+			
+			while parents.last().map(|&level| level >= descriptor.level()).unwrap_or(false) {
+				parents.pop();
+			}
+			
+			let level = parents.len() as Level;
+			parents.push(descriptor.level());
+			
+			buf.string.push_str(&self.string[part.clone()]);
+			buf.descriptors.push(Descriptor::new(part.end - part.start, level, descriptor.meta() && level > 0, descriptor.kind, descriptor.style));
+			buf.level = level;
+		}
+		
+		// The kept descriptors are always contiguous.
+		if let (Some(&(first, _)), Some(&(last, _))) = (kept.first(), kept.last()) {
+			for span in &self.interactions {
+				let (start, end) = (cmp::max(span.range.start, first), cmp::min(span.range.end, last + 1));
+				
+				if start < end {
+					buf.interactions.push(Span { range: start - first .. end - first, interaction: span.interaction.clone() });
+				}
+			}
+		}
+		
+		buf
+	}
+	
+	/// Appends a copy of another buffer to the end of this one. The top level components of the other buffer are placed at the top level.
+	pub fn append(&mut self, other: &ChatBuf) {
+		let offset = self.descriptors.len();
+		
+		self.string.push_str(&other.string);
+		self.descriptors.extend_from_slice(&other.descriptors);
+		
+		for span in &other.interactions {
+			self.interactions.push(Span { range: span.range.start + offset .. span.range.end + offset, interaction: span.interaction.clone() });
+		}
+		
+		if !other.descriptors.is_empty() {
+			self.level = other.level;
+		}
+	}
+	
+	/// Returns the length of the text of all components, in bytes.
+	pub fn len(&self) -> usize {
+		self.string.len()
+	}
+	
+	/// Returns whether the components have no text.
+	pub fn is_empty(&self) -> bool {
+		self.string.is_empty()
+	}
+	
+	/// Attaches an interaction to a range of descriptors, usually a component and all of its children.
+	/// Each field of an interaction is inherited seperately, and interactions added later take priority. 
+	/// Because of this, ranges must be added in order of their start, and must either nest inside of each other or not overlap at all.
+	pub fn interact(&mut self, descriptors: Range<usize>, interaction: Interaction) {
+		if let Some(last) = self.interactions.last() {
+			if descriptors.start < last.range.start || (descriptors.start < last.range.end && descriptors.end > last.range.end) {
+				panic!("Interaction ranges must be added in order, and must nest inside of each other or not overlap!");
+			}
+		}
+		
+		self.interactions.push(Span { range: descriptors, interaction: interaction });
+	}
+	
+	/// Returns the interactions in the order they were added, along with the range of descriptors they apply to.
+	pub fn interactions(&self) -> Iter<Span> {
+		self.interactions.iter()
+	}
+	
+	/// Returns the interaction that starts at the descriptor `index`, which is the one specified by the component itself.
+	pub fn own_interaction(&self, index: usize) -> Option<&Interaction> {
+		self.interactions.iter().find(|span| span.range.start == index).map(|span| &span.interaction)
+	}
+	
+	/// Resolves the interaction of the component at the descriptor `index`. Each field comes from the innermost interaction that specifies it.
+	pub fn interaction(&self, index: usize) -> InteractionRef {
+		let mut resolved = InteractionRef::none();
+		
+		for span in self.interactions.iter().take_while(|span| span.range.start <= index) {
+			if index >= span.range.end {
+				continue;
+			}
+			
+			let interaction = &span.interaction;
+			
+			if let Some(ref insert) = interaction.insert { resolved.insert = Some(insert) }
+			if let Some(ref click) = interaction.click { resolved.click = Some(click) }
+			if let Some(ref hover) = interaction.hover { resolved.hover = Some(hover) }
+		}
+		
+		resolved
+	}
+	
+	/// Returns the interaction at a byte index into the text of the components, or None if there is no interaction there.
+	pub fn interaction_at_byte(&self, byte: usize) -> Option<InteractionRef> {
+		let mut start = 0;
+		
+		for (index, descriptor) in self.descriptors.iter().enumerate() {
+			let end = start + descriptor.len as usize;
+			
+			if byte < end {
+				let interaction = self.interaction(index);
+				
+				return if interaction.is_none() { None } else { Some(interaction) };
+			}
+			
+			start = end;
+		}
+		
+		None
+	}
+	
+	/// Returns the interaction at a character index into the text of the components, or None if there is no interaction there.
+	pub fn interaction_at_char(&self, character: usize) -> Option<InteractionRef> {
+		self.string.char_indices().nth(character).and_then(|(byte, _)| self.interaction_at_byte(byte))
+	}
 	
 	pub fn components(&self) -> Components {
 		Components {
@@ -121,7 +442,7 @@ impl<'a> Iterator for Components<'a> {
 			self.head = rest;
 			
 			let mode = if self.level > descriptor.level() {
-				Mode::Shallower(descriptor.level() - self.level)
+				Mode::Shallower(self.level - descriptor.level())
 			} else if self.level == descriptor.level() {
 				Mode::Level
 			} else {
@@ -141,8 +462,8 @@ impl<'a> Iterator for Components<'a> {
 	}
 }
 
-// 8 bytes
-#[derive(Debug)]
+// 10 bytes, as a Style is 5 bytes like in the 6 byte descriptors of PlainBuf
+#[derive(Debug, Copy, Clone, PartialEq)]
 struct Descriptor {
 	len: u16,
 	lvl: Level,
@@ -169,9 +490,67 @@ impl Descriptor {
 	}
 }
 
+/// The result of a conversion into a representation that cannot store interactions.
+#[derive(Debug)]
+pub struct Lossy<T> {
+	pub value: T,
+	/// The interactions that were dropped, along with the byte range of the converted text that they applied to.
+	pub dropped: Vec<(Range<usize>, Interaction)>
+}
+
+impl<T> Lossy<T> {
+	pub fn is_lossless(&self) -> bool {
+		self.dropped.is_empty()
+	}
+	
+	/// Returns the converted value if nothing was dropped, or the whole result otherwise.
+	pub fn lossless(self) -> Result<T, Self> {
+		if self.is_lossless() { Ok(self.value) } else { Err(self) }
+	}
+}
+
+/// An interaction, along with the range of descriptors that it applies to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+	range: Range<usize>,
+	interaction: Interaction
+}
+
+impl Span {
+	pub fn range(&self) -> Range<usize> {
+		self.range.clone()
+	}
+	
+	pub fn interaction(&self) -> &Interaction {
+		&self.interaction
+	}
+}
+
+/// An interaction resolved from the interactions of a component and its parents.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InteractionRef<'a> {
+	pub insert: Option<&'a str>,
+	pub click: Option<&'a ClickEvent>,
+	pub hover: Option<&'a HoverEvent>
+}
+
+impl<'a> InteractionRef<'a> {
+	pub fn none() -> Self {
+		InteractionRef {
+			insert: None,
+			click: None,
+			hover: None
+		}
+	}
+	
+	pub fn is_none(&self) -> bool {
+		self.insert.is_none() && self.click.is_none() && self.hover.is_none()
+	}
+}
+
 // INTER
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct Component<'a> {
 	text: &'a str,
 	kind: Kind,
@@ -195,6 +574,27 @@ impl<'a> Component<'a> {
 		}
 	}
 	
+	pub fn text(&self) -> &'a str {
+		self.text
+	}
+	
+	pub fn kind(&self) -> Kind {
+		self.kind
+	}
+	
+	pub fn style(&self) -> Style {
+		self.style
+	}
+	
+	pub fn mode(&self) -> Mode {
+		self.mode
+	}
+	
+	/// Whether this component is part of the payload of its parent, such as a translation argument, instead of a member of its `extra` array.
+	pub fn meta(&self) -> bool {
+		self.meta
+	}
+	
 	pub fn to_owned(&self) -> ComponentBuf {
 		ComponentBuf {
 			text: self.text.to_owned(),
@@ -225,4 +625,69 @@ impl ComponentBuf {
 			meta: self.meta
 		}
 	}
-}
\ No newline at end of file
+}
+#[cfg(test)]
+fn summarize(buf: &ChatBuf) -> Vec<(&str, Kind, Mode, bool)> {
+	buf.components().map(|c| (c.text(), c.kind(), c.mode(), c.meta())).collect()
+}
+
+#[test]
+fn test_edit() {
+	let source = r#"{"text":"ab","clickEvent":{"action":"run_command","value":"/a"},"extra":[{"translate":"key","with":["cé",{"text":"d","insertion":"i"}]},"ef"]}"#;
+	let buf = ChatBuf::from_json(source).unwrap();
+	
+	// Text: "ab" "key" "cé" "d" "ef"
+	let mut truncated = buf.clone();
+	truncated.truncate_chars(7);
+	assert_eq!(summarize(&truncated), vec![("ab", Kind::Text, Mode::Level, false), ("key", Kind::Translate, Mode::Deeper, false), ("cé", Kind::Text, Mode::Deeper, true)]);
+	assert_eq!(truncated.interactions().map(|span| span.range()).collect::<Vec<_>>(), vec![0..3]);
+	
+	// Translation keys cannot be cut.
+	truncated.truncate(4);
+	assert_eq!(summarize(&truncated), vec![("ab", Kind::Text, Mode::Level, false)]);
+	
+	assert_eq!(truncated.pop(), Some(("ab".to_owned(), Kind::Text, buf.components().next().unwrap().style())));
+	assert_eq!(truncated.pop(), None);
+	assert!(truncated.interactions().next().is_none());
+	
+	// Arguments lose their parent, and are moved up.
+	let slice = buf.slice(6..11);
+	assert_eq!(summarize(&slice), vec![("é", Kind::Text, Mode::Level, false), ("d", Kind::Text, Mode::Level, false), ("ef", Kind::Text, Mode::Level, false)]);
+	assert_eq!(slice.interactions().map(|span| span.range()).collect::<Vec<_>>(), vec![0..3, 1..2]);
+	assert_eq!(slice.interaction_at_byte(2).unwrap().insert, Some("i"));
+	
+	let mut joined = buf.slice(0..2);
+	joined.append(&buf.slice(2..buf.len()));
+	assert_eq!(summarize(&joined), vec![
+		("ab", Kind::Text, Mode::Level, false), 
+		("key", Kind::Translate, Mode::Level, false), 
+		("cé", Kind::Text, Mode::Deeper, true), 
+		("d", Kind::Text, Mode::Level, true), 
+		("ef", Kind::Text, Mode::Shallower(1), false)
+	]);
+	assert_eq!(joined.interactions().map(|span| span.range()).collect::<Vec<_>>(), vec![0..1, 1..5, 3..4]);
+	
+	let reparsed = ChatBuf::from_json(&joined.into_json_flattened()).unwrap();
+	for byte in 0..joined.len() {
+		assert_eq!(reparsed.interaction_at_byte(byte), joined.interaction_at_byte(byte));
+	}
+}
+
+#[test]
+fn test_plain_conversion() {
+	let plain = "§cab§lcd§re".parse::<PlainBuf>().unwrap();
+	let buf = ChatBuf::from_plain(&plain);
+	
+	assert_eq!(summarize(&buf), vec![("ab", Kind::Text, Mode::Level, false), ("cd", Kind::Text, Mode::Level, false), ("e", Kind::Text, Mode::Level, false)]);
+	assert_eq!(buf.to_plain().lossless().unwrap().iter().collect::<Vec<_>>(), plain.iter().collect::<Vec<_>>());
+	
+	let long = "é".repeat(40000).parse::<PlainBuf>().unwrap();
+	assert_eq!(ChatBuf::from_plain(&long).components().map(|c| c.text().len()).collect::<Vec<_>>(), vec![65534, 14466]);
+	
+	let buf = ChatBuf::from_json(r#"["",{"text":"a","color":"red","clickEvent":{"action":"run_command","value":"/a"}},{"translate":"key","with":["hidden",{"text":"x","extra":["y"]}]},{"score":{"name":"@p","objective":"o","value":"3"},"insertion":"i"},"z"]"#).unwrap();
+	let lossy = buf.to_plain();
+	
+	assert_eq!(lossy.value.unstyled(), "akey3z");
+	assert_eq!(lossy.dropped.iter().map(|&(ref range, ref interaction)| (range.clone(), interaction.insert.is_some())).collect::<Vec<_>>(), vec![(0..1, false), (4..5, true)]);
+	assert!(lossy.lossless().is_err());
+}
@@ -1,4 +1,10 @@
-enum Field {
+use text::repr::flat::{ChatBuf, Component, Kind, Mode, Interaction, ClickEvent, HoverEvent, MAX_NESTING};
+use text::style::{Style, Color};
+use text::formatter::transform::java_double;
+use serde::de::{Deserializer, Deserialize, DeserializeSeed, Error, Visitor, SeqAccess, MapAccess, IgnoredAny};
+use std::fmt;
+
+pub enum Field {
 	Bold,
 	Italic,
 	Underlined,
@@ -18,7 +24,7 @@ enum Field {
 }
 
 impl Field {
-	fn from_str(key: &str) -> Option<Self> {
+	pub fn from_str(key: &str) -> Option<Self> {
 		Some(match key {
 			"bold" => Field::Bold,
 			"italic" => Field::Italic,
@@ -40,7 +46,7 @@ impl Field {
 		})
 	}
 	
-	fn to_str(&self) -> &'static str {
+	pub fn to_str(&self) -> &'static str {
 		match *self {
 			Field::Bold => "bold",
 			Field::Italic => "italic",
@@ -62,13 +68,13 @@ impl Field {
 	}
 }
 
-enum EventField {
+pub enum EventField {
 	Action,
 	Value
 }
 
 impl EventField {
-	fn from_str(key: &str) -> Option<Self> {
+	pub fn from_str(key: &str) -> Option<Self> {
 		Some(match key {
 			"action" => EventField::Action,
 			"value" => EventField::Value,
@@ -76,7 +82,7 @@ impl EventField {
 		})
 	}
 	
-	fn to_str(&self) -> &'static str {
+	pub fn to_str(&self) -> &'static str {
 		match *self {
 			EventField::Action => "action",
 			EventField::Value => "value"
@@ -84,14 +90,14 @@ impl EventField {
 	}
 }
 
-enum ScoreField {
+pub enum ScoreField {
 	Name,
 	Objective,
 	Value
 }
 
 impl ScoreField {
-	fn from_str(key: &str) -> Option<Self> {
+	pub fn from_str(key: &str) -> Option<Self> {
 		Some(match key {
 			"name" => ScoreField::Name,
 			"objective" => ScoreField::Objective,
@@ -100,11 +106,705 @@ impl ScoreField {
 		})
 	}
 	
-	fn to_str(&self) -> &'static str {
+	pub fn to_str(&self) -> &'static str {
 		match *self {
 			ScoreField::Name => "name",
 			ScoreField::Objective => "objective",
 			ScoreField::Value => "value"
 		}
 	}
-}
\ No newline at end of file
+}
+
+/// Style properties that were explicitly specified on a component. Properties that are not specified are inherited from the parent.
+#[derive(Debug, Copy, Clone, Default)]
+struct PartialStyle {
+	bold: Option<bool>,
+	italic: Option<bool>,
+	underlined: Option<bool>,
+	strikethrough: Option<bool>,
+	obfuscated: Option<bool>,
+	color: Option<Color>
+}
+
+impl PartialStyle {
+	fn resolve(&self, parent: Style) -> Style {
+		let mut flags = parent.flags;
+		
+		if let Some(bold) = self.bold 					{ flags = flags.set_bold(bold) }
+		if let Some(italic) = self.italic 				{ flags = flags.set_italic(italic) }
+		if let Some(underlined) = self.underlined 		{ flags = flags.set_underline(underlined) }
+		if let Some(strikethrough) = self.strikethrough { flags = flags.set_strikethrough(strikethrough) }
+		if let Some(obfuscated) = self.obfuscated 		{ flags = flags.set_obfuscate(obfuscated) }
+		
+		Style {
+			color: self.color.unwrap_or(parent.color),
+			flags: flags
+		}
+	}
+}
+
+struct Entry {
+	len: usize,
+	level: usize,
+	meta: bool,
+	kind: Kind,
+	style: PartialStyle,
+	interaction: Interaction
+}
+
+/// A sequence of decoded components that has not been written to a ChatBuf yet.
+/// JSON objects may list `extra` before the style and text of the component itself (the vanilla serializer does this), 
+/// so styles stay unresolved until the whole tree is known. Levels are relative to the start of the fragment.
+struct Fragment {
+	string: String,
+	entries: Vec<Entry>
+}
+
+impl Fragment {
+	fn new() -> Self {
+		Fragment {
+			string: String::new(),
+			entries: Vec::new()
+		}
+	}
+	
+	fn push(&mut self, text: &str, level: usize, meta: bool, kind: Kind, style: PartialStyle) {
+		self.string.push_str(text);
+		self.entries.push(Entry { len: text.len(), level: level, meta: meta, kind: kind, style: style, interaction: Interaction::none() });
+	}
+	
+	/// Appends a fragment as the children of the last component at `level`. The top level components of the child fragment are marked with `meta`.
+	fn append_children(&mut self, children: Fragment, level: usize, meta: bool) {
+		self.string.push_str(&children.string);
+		
+		for entry in children.entries {
+			self.entries.push(Entry {
+				len: entry.len,
+				level: entry.level + level + 1,
+				meta: if entry.level == 0 { meta } else { entry.meta },
+				kind: entry.kind,
+				style: entry.style,
+				interaction: entry.interaction
+			});
+		}
+	}
+	
+	/// Resolves the styles and interactions of the fragment. Style properties that the top level components do not set are inherited from `parent`.
+	fn into_chat(self, parent: Style) -> Result<ChatBuf, String> {
+		let mut buf = ChatBuf::with_capacity(self.string.len(), self.entries.len());
+		let mut styles: Vec<Style> = Vec::new();
+		let mut head = &self.string as &str;
+		let mut current = 0;
+		let total = self.entries.len();
+		
+		// Interactions apply to the component and all of its children, so they end at the next component that is not deeper.
+		let mut open: Vec<(usize, usize, Interaction)> = Vec::new();
+		let mut spans = Vec::new();
+		
+		for (index, entry) in self.entries.into_iter().enumerate() {
+			if entry.level > MAX_NESTING as usize {
+				return Err(format!("chat components may only be nested to level {}", MAX_NESTING));
+			}
+			
+			let mode = if entry.level > current {
+				Mode::Deeper
+			} else if entry.level == current {
+				Mode::Level
+			} else {
+				Mode::Shallower((current - entry.level) as u8)
+			};
+			
+			current = entry.level;
+			
+			styles.truncate(entry.level);
+			let inherited = styles.last().map(|style| *style).unwrap_or(parent);
+			let style = entry.style.resolve(inherited);
+			styles.push(style);
+			
+			let (text, rest) = head.split_at(entry.len);
+			head = rest;
+			
+			let component = Component::new(text, entry.kind, style, mode, entry.meta)
+				.ok_or_else(|| format!("component text is {} bytes long, which is too long to store", text.len()))?;
+			
+			buf.push(component);
+			
+			while open.last().map(|&(_, level, _)| level >= entry.level).unwrap_or(false) {
+				let (start, _, interaction) = open.pop().unwrap();
+				spans.push((start..index, interaction));
+			}
+			
+			if !entry.interaction.is_none() {
+				open.push((index, entry.level, entry.interaction));
+			}
+		}
+		
+		for (start, _, interaction) in open.drain(..) {
+			spans.push((start..total, interaction));
+		}
+		
+		spans.sort_by_key(|&(ref range, _)| range.start);
+		
+		for (range, interaction) in spans {
+			buf.interact(range, interaction);
+		}
+		
+		Ok(buf)
+	}
+}
+
+impl<'de> Deserialize<'de> for ChatBuf {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		deserialize_in(deserializer, Style::new())
+	}
+}
+
+/// Decodes a chat component whose unset style properties are inherited from `parent`.
+pub fn deserialize_in<'de, D>(deserializer: D, parent: Style) -> Result<ChatBuf, D::Error> where D: Deserializer<'de> {
+	let mut fragment = Fragment::new();
+	
+	ComponentSeed { target: &mut fragment, level: 0 }.deserialize(deserializer)?;
+	
+	fragment.into_chat(parent).map_err(D::Error::custom)
+}
+
+/// Decodes a single component (a primitive, array, or object) and appends it, along with its children, to the target at the specified level.
+struct ComponentSeed<'a> {
+	target: &'a mut Fragment,
+	level: usize
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for ComponentSeed<'a> {
+	type Value = ();
+	
+	fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error> where D: Deserializer<'de> {
+		deserializer.deserialize_any(self)
+	}
+}
+
+impl<'a> ComponentSeed<'a> {
+	fn primitive<E>(self, text: &str) -> Result<(), E> where E: Error {
+		self.target.push(text, self.level, false, Kind::Text, PartialStyle::default());
+		Ok(())
+	}
+}
+
+impl<'a, 'de> Visitor<'de> for ComponentSeed<'a> {
+	type Value = ();
+	
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a chat component: an object, a non-empty array of components, or a primitive")
+	}
+	
+	fn visit_str<E>(self, v: &str) -> Result<(), E> where E: Error {
+		self.primitive(v)
+	}
+	
+	fn visit_bool<E>(self, v: bool) -> Result<(), E> where E: Error {
+		self.primitive(if v {"true"} else {"false"})
+	}
+	
+	fn visit_i64<E>(self, v: i64) -> Result<(), E> where E: Error {
+		self.primitive(&v.to_string())
+	}
+	
+	fn visit_u64<E>(self, v: u64) -> Result<(), E> where E: Error {
+		self.primitive(&v.to_string())
+	}
+	
+	fn visit_f64<E>(self, v: f64) -> Result<(), E> where E: Error {
+		// Gson keeps the text of the number, but serde_json only passes on its value, so it is written like Java's `Double.toString` instead.
+		self.primitive(&java_double(v))
+	}
+	
+	fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error> where A: SeqAccess<'de> {
+		// The first element acts as the parent of the rest of the elements.
+		let level = self.level;
+		
+		if seq.next_element_seed(ComponentSeed { target: self.target, level: level })?.is_none() {
+			return Err(A::Error::custom("unexpected empty array of components"));
+		}
+		
+		while let Some(()) = seq.next_element_seed(ComponentSeed { target: self.target, level: level + 1 })? {}
+		
+		Ok(())
+	}
+	
+	fn visit_map<A>(self, mut map: A) -> Result<(), A::Error> where A: MapAccess<'de> {
+		let mut style = PartialStyle::default();
+		let mut interaction = Interaction::none();
+		
+		let mut text = None;
+		let mut translate = None;
+		let mut with = None;
+		let mut score = None;
+		let mut selector = None;
+		let mut keybind = None;
+		let mut extra = None;
+		
+		while let Some(FieldKey(field)) = map.next_key()? {
+			let field = match field {
+				Some(field) => field,
+				None => { map.next_value::<IgnoredAny>()?; continue }
+			};
+			
+			match field {
+				Field::Bold 			=> style.bold = Some(map.next_value::<BoolLike>()?.0),
+				Field::Italic 			=> style.italic = Some(map.next_value::<BoolLike>()?.0),
+				Field::Underlined 		=> style.underlined = Some(map.next_value::<BoolLike>()?.0),
+				Field::Strikethrough 	=> style.strikethrough = Some(map.next_value::<BoolLike>()?.0),
+				Field::Obfuscated 		=> style.obfuscated = Some(map.next_value::<BoolLike>()?.0),
+				Field::Color 			=> style.color = map.next_value::<ColorName>()?.0,
+				Field::Insertion 		=> interaction.insert = Some(map.next_value::<StringLike>()?.0),
+				Field::ClickEvent 		=> interaction.click = map.next_value::<ClickEventValue>()?.0,
+				Field::HoverEvent 		=> interaction.hover = map.next_value::<HoverEventValue>()?.0,
+				Field::Extra => {
+					let mut children = Fragment::new();
+					
+					if map.next_value_seed(ChildrenSeed { target: &mut children })? == 0 {
+						return Err(A::Error::custom("unexpected empty array of components"));
+					}
+					
+					extra = Some(children);
+				},
+				Field::Text 	=> text = Some(map.next_value::<StringLike>()?.0),
+				Field::Translate=> translate = Some(map.next_value::<StringLike>()?.0),
+				Field::With 	=> {
+					let mut children = Fragment::new();
+					map.next_value_seed(ChildrenSeed { target: &mut children })?;
+					
+					with = Some(children);
+				},
+				Field::Score 	=> score = Some(map.next_value::<Score>()?),
+				Field::Selector => selector = Some(map.next_value::<StringLike>()?.0),
+				Field::Keybind 	=> keybind = Some(map.next_value::<StringLike>()?.0)
+			}
+		}
+		
+		let level = self.level;
+		let target = self.target;
+		let own = target.entries.len();
+		
+		// Decode in the same order as the vanilla client: text, translation, score, selector, keybind.
+		if let Some(text) = text {
+			target.push(&text, level, false, Kind::Text, style);
+		} else if let Some(translate) = translate {
+			target.push(&translate, level, false, Kind::Translate, style);
+			
+			if let Some(with) = with {
+				target.append_children(with, level, true);
+			}
+		} else if let Some(score) = score {
+			target.push(&score.name, level, false, Kind::ScoreName, style);
+			target.push(&score.objective, level + 1, true, Kind::ScoreObjective, PartialStyle::default());
+			
+			if let Some(value) = score.value {
+				target.push(&value, level + 1, true, Kind::ScoreValue, PartialStyle::default());
+			}
+		} else if let Some(selector) = selector {
+			target.push(&selector, level, false, Kind::Selector, style);
+		} else if let Some(keybind) = keybind {
+			target.push(&keybind, level, false, Kind::Keybind, style);
+		} else {
+			return Err(A::Error::custom("component has none of the fields text, translate, score, selector, or keybind"));
+		}
+		
+		target.entries[own].interaction = interaction;
+		
+		if let Some(extra) = extra {
+			target.append_children(extra, level, false);
+		}
+		
+		Ok(())
+	}
+}
+
+/// Decodes an array of components, appending each of them at the top level of the target. Returns the number of components.
+struct ChildrenSeed<'a> {
+	target: &'a mut Fragment
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for ChildrenSeed<'a> {
+	type Value = usize;
+	
+	fn deserialize<D>(self, deserializer: D) -> Result<usize, D::Error> where D: Deserializer<'de> {
+		deserializer.deserialize_seq(self)
+	}
+}
+
+impl<'a, 'de> Visitor<'de> for ChildrenSeed<'a> {
+	type Value = usize;
+	
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("an array of chat components")
+	}
+	
+	fn visit_seq<A>(self, mut seq: A) -> Result<usize, A::Error> where A: SeqAccess<'de> {
+		let mut count = 0;
+		
+		while let Some(()) = seq.next_element_seed(ComponentSeed { target: self.target, level: 0 })? {
+			count += 1;
+		}
+		
+		Ok(count)
+	}
+}
+
+struct FieldKey(Option<Field>);
+
+impl<'de> Deserialize<'de> for FieldKey {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		struct FieldVisitor;
+		impl<'de> Visitor<'de> for FieldVisitor {
+			type Value = FieldKey;
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a chat component field name")
+			}
+			
+			fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: Error {
+				Ok(FieldKey(Field::from_str(v)))
+			}
+		}
+		
+		deserializer.deserialize_identifier(FieldVisitor)
+	}
+}
+
+/// A JSON primitive read as a string, mirroring `JsonElement.getAsString()`.
+pub struct StringLike(pub String);
+
+impl<'de> Deserialize<'de> for StringLike {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		struct StringVisitor;
+		impl<'de> Visitor<'de> for StringVisitor {
+			type Value = StringLike;
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a string or another JSON primitive")
+			}
+			
+			fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: Error {
+				Ok(StringLike(v.to_owned()))
+			}
+			
+			fn visit_string<E>(self, v: String) -> Result<Self::Value, E> where E: Error {
+				Ok(StringLike(v))
+			}
+			
+			fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> where E: Error {
+				Ok(StringLike(if v {"true"} else {"false"}.to_owned()))
+			}
+			
+			fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> where E: Error {
+				Ok(StringLike(v.to_string()))
+			}
+			
+			fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where E: Error {
+				Ok(StringLike(v.to_string()))
+			}
+			
+			fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> where E: Error {
+				Ok(StringLike(java_double(v)))
+			}
+		}
+		
+		deserializer.deserialize_any(StringVisitor)
+	}
+}
+
+/// A JSON primitive read as a boolean, mirroring `JsonElement.getAsBoolean()`: strings are parsed with `Boolean.parseBoolean`, and numbers are false.
+pub struct BoolLike(pub bool);
+
+impl<'de> Deserialize<'de> for BoolLike {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		struct BoolVisitor;
+		impl<'de> Visitor<'de> for BoolVisitor {
+			type Value = BoolLike;
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a boolean or another JSON primitive")
+			}
+			
+			fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> where E: Error {
+				Ok(BoolLike(v))
+			}
+			
+			fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: Error {
+				Ok(BoolLike(v.eq_ignore_ascii_case("true")))
+			}
+			
+			fn visit_i64<E>(self, _: i64) -> Result<Self::Value, E> where E: Error {
+				Ok(BoolLike(false))
+			}
+			
+			fn visit_u64<E>(self, _: u64) -> Result<Self::Value, E> where E: Error {
+				Ok(BoolLike(false))
+			}
+			
+			fn visit_f64<E>(self, _: f64) -> Result<Self::Value, E> where E: Error {
+				Ok(BoolLike(false))
+			}
+		}
+		
+		deserializer.deserialize_any(BoolVisitor)
+	}
+}
+
+/// The value of the `color` field. Unknown color names are ignored, like in vanilla, and the color is inherited instead.
+pub struct ColorName(pub Option<Color>);
+
+impl<'de> Deserialize<'de> for ColorName {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		let StringLike(name) = StringLike::deserialize(deserializer)?;
+		
+		Ok(ColorName(Color::from_name(&name)))
+	}
+}
+
+struct Score {
+	name: String,
+	objective: String,
+	value: Option<String>
+}
+
+impl<'de> Deserialize<'de> for Score {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		struct ScoreVisitor;
+		impl<'de> Visitor<'de> for ScoreVisitor {
+			type Value = Score;
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a score object with a name and an objective")
+			}
+			
+			fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error> where A: MapAccess<'de> {
+				let mut name = None;
+				let mut objective = None;
+				let mut value = None;
+				
+				while let Some(key) = map.next_key::<String>()? {
+					match ScoreField::from_str(&key) {
+						Some(ScoreField::Name) 		=> name = Some(map.next_value::<StringLike>()?.0),
+						Some(ScoreField::Objective) => objective = Some(map.next_value::<StringLike>()?.0),
+						Some(ScoreField::Value) 	=> value = Some(map.next_value::<StringLike>()?.0),
+						None => { map.next_value::<IgnoredAny>()?; }
+					}
+				}
+				
+				Ok(Score {
+					name: name.ok_or_else(|| A::Error::missing_field("name"))?,
+					objective: objective.ok_or_else(|| A::Error::missing_field("objective"))?,
+					value: value
+				})
+			}
+		}
+		
+		deserializer.deserialize_map(ScoreVisitor)
+	}
+}
+
+/// The value of the `clickEvent` field. Like in vanilla, events without an action or a value are ignored.
+struct ClickEventValue(Option<ClickEvent>);
+
+impl<'de> Deserialize<'de> for ClickEventValue {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		struct ClickEventVisitor;
+		impl<'de> Visitor<'de> for ClickEventVisitor {
+			type Value = ClickEventValue;
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a click event object with an action and a value")
+			}
+			
+			fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error> where A: MapAccess<'de> {
+				let mut action = None;
+				let mut value = None;
+				
+				while let Some(key) = map.next_key::<String>()? {
+					match EventField::from_str(&key) {
+						Some(EventField::Action) 	=> action = Some(map.next_value::<StringLike>()?.0),
+						Some(EventField::Value) 	=> value = Some(map.next_value::<StringLike>()?.0),
+						None => { map.next_value::<IgnoredAny>()?; }
+					}
+				}
+				
+				Ok(ClickEventValue(match (action, value) {
+					(Some(action), Some(value)) => Some(ClickEvent::new(&action, value)),
+					_ => None
+				}))
+			}
+		}
+		
+		deserializer.deserialize_map(ClickEventVisitor)
+	}
+}
+
+/// The value of the `hoverEvent` field. Vanilla decodes the value as a chat component for every action, 
+/// and actions other than `show_text` use the unformatted text of the component as their data.
+struct HoverEventValue(Option<HoverEvent>);
+
+impl<'de> Deserialize<'de> for HoverEventValue {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		struct HoverEventVisitor;
+		impl<'de> Visitor<'de> for HoverEventVisitor {
+			type Value = HoverEventValue;
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a hover event object with an action and a value")
+			}
+			
+			fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error> where A: MapAccess<'de> {
+				let mut action = None;
+				let mut value = None;
+				
+				while let Some(key) = map.next_key::<String>()? {
+					match EventField::from_str(&key) {
+						Some(EventField::Action) 	=> action = Some(map.next_value::<StringLike>()?.0),
+						Some(EventField::Value) 	=> value = Some(map.next_value::<ChatBuf>()?),
+						None => { map.next_value::<IgnoredAny>()?; }
+					}
+				}
+				
+				Ok(HoverEventValue(match (action, value) {
+					(Some(ref action), Some(value)) if action == "show_text" => Some(HoverEvent::ShowText(value)),
+					(Some(action), Some(value)) => {
+						let data = value.components().filter(|c| c.kind() == Kind::Text).map(|c| c.text()).collect::<String>();
+						
+						Some(HoverEvent::from_data(&action, data))
+					},
+					_ => None
+				}))
+			}
+		}
+		
+		deserializer.deserialize_map(HoverEventVisitor)
+	}
+}
+
+#[cfg(test)]
+fn summarize(json: &str) -> Vec<(String, Kind, Style, Mode, bool)> {
+	ChatBuf::from_json(json).unwrap().components().map(|c| (c.text().to_owned(), c.kind(), c.style(), c.mode(), c.meta())).collect()
+}
+
+#[test]
+fn test_from_json_vanilla() {
+	use text::style::{StyleFlags, PaletteColor};
+	
+	let yellow = Style { color: Color::Palette(PaletteColor::Yellow), flags: StyleFlags::none() };
+	
+	// Join message as sent by a vanilla 1.12 server.
+	assert_eq!(summarize(r#"{"translate":"multiplayer.player.joined","with":[{"insertion":"Steve","clickEvent":{"action":"suggest_command","value":"/tell Steve "},"hoverEvent":{"action":"show_entity","value":{"text":"{name:\"Steve\",id:\"0c2f8d4e-1111-2222-3333-444455556666\"}"}},"text":"Steve"}],"color":"yellow"}"#), vec![
+		("multiplayer.player.joined".to_owned(), Kind::Translate, yellow, Mode::Level, false),
+		("Steve".to_owned(), Kind::Text, yellow, Mode::Deeper, true)
+	]);
+	
+	// The vanilla serializer writes `extra` before `text`, so styles must be resolved after the whole object is read.
+	let gold = Style { color: Color::Palette(PaletteColor::Gold), flags: StyleFlags::none() };
+	let bold_gold = Style { color: Color::Palette(PaletteColor::Gold), flags: StyleFlags::none().set_bold(true) };
+	
+	assert_eq!(summarize(r#"{"extra":[{"text":"[Server] "},{"bold":true,"extra":[{"bold":false,"text":"!"}],"text":"Welcome"}],"color":"gold","text":""}"#), vec![
+		("".to_owned(), Kind::Text, gold, Mode::Level, false),
+		("[Server] ".to_owned(), Kind::Text, gold, Mode::Deeper, false),
+		("Welcome".to_owned(), Kind::Text, bold_gold, Mode::Level, false),
+		("!".to_owned(), Kind::Text, gold, Mode::Deeper, false)
+	]);
+	
+	// Servers since 1.16 send RGB colors as hex, read like Integer.parseInt. Malformed colors are ignored like unknown names.
+	let orange = Style { color: Color::Rgb(0xFF, 0x80, 0x00), flags: StyleFlags::none() };
+	let short = Style { color: Color::Rgb(0x0F, 0xF8, 0x00), flags: StyleFlags::none() };
+	
+	assert_eq!(summarize(r##"{"text":"a","color":"#FF8000","extra":[{"text":"b","color":"#ff800"},{"text":"c","color":"#0a0b0G"},{"text":"d","color":"reset"}]}"##), vec![
+		("a".to_owned(), Kind::Text, orange, Mode::Level, false),
+		("b".to_owned(), Kind::Text, short, Mode::Deeper, false),
+		("c".to_owned(), Kind::Text, orange, Mode::Level, false),
+		("d".to_owned(), Kind::Text, Style::new(), Mode::Level, false)
+	]);
+}
+
+#[test]
+fn test_from_json_structure() {
+	use text::style::{StyleFlags, PaletteColor};
+	
+	let plain = Style::new();
+	let red = Style { color: Color::Palette(PaletteColor::Red), flags: StyleFlags::none() };
+	
+	// The first element of an array is the parent of the rest.
+	assert_eq!(summarize(r#"[{"text":"a","color":"red"},"b",["c",1,true,1.50,{"text":1e20}]]"#), vec![
+		("a".to_owned(), Kind::Text, red, Mode::Level, false),
+		("b".to_owned(), Kind::Text, red, Mode::Deeper, false),
+		("c".to_owned(), Kind::Text, red, Mode::Level, false),
+		("1".to_owned(), Kind::Text, red, Mode::Deeper, false),
+		("true".to_owned(), Kind::Text, red, Mode::Level, false),
+		("1.5".to_owned(), Kind::Text, red, Mode::Level, false),
+		("1.0E20".to_owned(), Kind::Text, red, Mode::Level, false)
+	]);
+	
+	assert_eq!(summarize(r#"{"text":"a","extra":[{"text":"b","extra":[{"text":"c","extra":["d"]}]},"e"]}"#), vec![
+		("a".to_owned(), Kind::Text, plain, Mode::Level, false),
+		("b".to_owned(), Kind::Text, plain, Mode::Deeper, false),
+		("c".to_owned(), Kind::Text, plain, Mode::Deeper, false),
+		("d".to_owned(), Kind::Text, plain, Mode::Deeper, false),
+		("e".to_owned(), Kind::Text, plain, Mode::Shallower(2), false)
+	]);
+	
+	assert_eq!(summarize(r#"{"score":{"name":"@p","objective":"kills","value":"3"},"extra":[" kills"]}"#), vec![
+		("@p".to_owned(), Kind::ScoreName, plain, Mode::Level, false),
+		("kills".to_owned(), Kind::ScoreObjective, plain, Mode::Deeper, true),
+		("3".to_owned(), Kind::ScoreValue, plain, Mode::Level, true),
+		(" kills".to_owned(), Kind::Text, plain, Mode::Level, false)
+	]);
+	
+	// Text takes priority over the other payloads, and unknown colors are inherited.
+	assert_eq!(summarize(r#"{"keybind":"key.jump","text":"t","color":"not_a_color","bold":"TRUE"}"#), vec![
+		("t".to_owned(), Kind::Text, Style { color: Color::Default, flags: StyleFlags::none().set_bold(true) }, Mode::Level, false)
+	]);
+	
+	assert_eq!(summarize(r#"{"selector":"@a[r=5]"}"#)[0].1, Kind::Selector);
+	assert_eq!(summarize(r#"{"keybind":"key.inventory"}"#)[0].1, Kind::Keybind);
+}
+
+#[test]
+fn test_from_json_errors() {
+	assert!(ChatBuf::from_json("[]").is_err());
+	assert!(ChatBuf::from_json("null").is_err());
+	assert!(ChatBuf::from_json("{}").is_err());
+	assert!(ChatBuf::from_json(r#"{"text":"a","extra":[]}"#).is_err());
+	assert!(ChatBuf::from_json(r#"{"score":{"name":"@p"}}"#).is_err());
+	assert!(ChatBuf::from_json(r#"{"text":"a""#).is_err());
+}
+
+#[test]
+fn test_from_json_interactions() {
+	let buf = ChatBuf::from_json(r#"{"translate":"chat.type.text","with":[{"insertion":"Steve","clickEvent":{"action":"suggest_command","value":"/tell Steve "},"hoverEvent":{"action":"show_entity","value":{"text":"{name:\"Steve\"}"}},"text":"Steve","extra":[{"text":"é","clickEvent":{"action":"change_page","value":"2"}}]},{"hoverEvent":{"action":"show_text","value":["a",{"text":"b","bold":true}]},"text":"hi"}]}"#).unwrap();
+	
+	// Descriptors: translation, "Steve", "é", "hi".
+	let spans = buf.interactions().map(|span| span.range()).collect::<Vec<_>>();
+	assert_eq!(spans, vec![1..3, 2..3, 3..4]);
+	
+	assert!(buf.interaction(0).is_none());
+	
+	let steve = buf.interaction(1);
+	assert_eq!(steve.insert, Some("Steve"));
+	assert_eq!(steve.click, Some(&ClickEvent::SuggestCommand("/tell Steve ".to_owned())));
+	assert_eq!(steve.hover, Some(&HoverEvent::ShowEntity("{name:\"Steve\"}".to_owned())));
+	
+	// Each field is inherited seperately.
+	let page = buf.interaction(2);
+	assert_eq!(page.insert, Some("Steve"));
+	assert_eq!(page.click, Some(&ClickEvent::ChangePage(2.0)));
+	assert_eq!(page.hover, steve.hover);
+	
+	match buf.interaction(3).hover {
+		Some(&HoverEvent::ShowText(ref text)) => assert_eq!(text, &ChatBuf::from_json(r#"["a",{"text":"b","bold":true}]"#).unwrap()),
+		ref other => panic!("expected show_text, got {:?}", other)
+	}
+	
+	// Byte and character indices: "chat.type.text" is 14 bytes, "Steve" is 5, and "é" is 2 bytes but 1 character.
+	assert_eq!(buf.interaction_at_byte(0), None);
+	assert_eq!(buf.interaction_at_byte(14), Some(steve));
+	assert_eq!(buf.interaction_at_byte(20), Some(page));
+	assert_eq!(buf.interaction_at_byte(21), Some(buf.interaction(3)));
+	assert_eq!(buf.interaction_at_byte(23), None);
+	assert_eq!(buf.interaction_at_char(19), Some(page));
+	assert_eq!(buf.interaction_at_char(20), Some(buf.interaction(3)));
+	
+	// Events without an action or a value are ignored, but malformed events are errors.
+	assert!(ChatBuf::from_json(r#"{"text":"a","clickEvent":{"action":"run_command"}}"#).unwrap().interactions().next().is_none());
+	assert!(ChatBuf::from_json(r#"{"text":"a","clickEvent":"run_command"}"#).is_err());
+	assert!(ChatBuf::from_json(r#"{"text":"a","hoverEvent":{"action":"show_text","value":[]}}"#).is_err());
+}
@@ -0,0 +1,469 @@
+use text::repr::flat::{ChatBuf, Component, Kind, Mode, Interaction, InteractionRef, HoverEvent};
+use text::repr::flat_deserialize::{Field, EventField, ScoreField};
+use text::style::Style;
+use serde::{Serializer, Serialize};
+use serde_json::{self, Value, Map};
+use std::iter::Peekable;
+
+/// A component along with its children, rebuilt from the levels of the descriptors.
+struct Node<'a> {
+	component: Component<'a>,
+	/// The interaction specified by the component itself.
+	own: Option<&'a Interaction>,
+	/// The interaction of the component after inheriting from the parents.
+	interaction: InteractionRef<'a>,
+	/// Translation arguments, or the objective and value of a score.
+	meta: Vec<Node<'a>>,
+	extra: Vec<Node<'a>>
+}
+
+/// Iterates over the components of a buffer along with their absolute levels.
+struct Leveled<'a, I> where I: Iterator<Item=Component<'a>> {
+	components: I,
+	level: usize
+}
+
+impl<'a, I> Iterator for Leveled<'a, I> where I: Iterator<Item=Component<'a>> {
+	type Item = (usize, Component<'a>);
+	
+	fn next(&mut self) -> Option<Self::Item> {
+		self.components.next().map(|component| {
+			match component.mode() {
+				Mode::Level => (),
+				Mode::Deeper => self.level += 1,
+				Mode::Shallower(reduction) => self.level -= ::std::cmp::min(self.level, reduction as usize)
+			};
+			
+			(self.level, component)
+		})
+	}
+}
+
+fn forest(buf: &ChatBuf) -> Vec<Node> {
+	let mut leveled = Leveled { components: buf.components(), level: 0 }.enumerate().peekable();
+	
+	children(buf, &mut leveled, 0)
+}
+
+fn children<'a, I>(buf: &'a ChatBuf, iter: &mut Peekable<I>, level: usize) -> Vec<Node<'a>> where I: Iterator<Item=(usize, (usize, Component<'a>))> {
+	let mut nodes = Vec::new();
+	
+	loop {
+		let current = match iter.peek() {
+			Some(&(_, (current, _))) if current >= level => current,
+			_ => break
+		};
+		
+		let (index, (_, component)) = iter.next().unwrap();
+		let (meta, extra) = children(buf, iter, current + 1).into_iter().partition(|node| node.component.meta());
+		
+		nodes.push(Node { 
+			component: component, 
+			own: buf.own_interaction(index), 
+			interaction: buf.interaction(index), 
+			meta: meta, 
+			extra: extra 
+		});
+	}
+	
+	nodes
+}
+
+/// Writes the style properties of `style` that differ from the inherited `parent` style.
+pub fn write_style(map: &mut Map<String, Value>, style: Style, parent: Style) {
+	let (flags, inherited) = (style.flags, parent.flags);
+	
+	if flags.bold() != inherited.bold() 					{ map.insert(Field::Bold.to_str().to_owned(), Value::Bool(flags.bold())); }
+	if flags.italic() != inherited.italic() 				{ map.insert(Field::Italic.to_str().to_owned(), Value::Bool(flags.italic())); }
+	if flags.underline() != inherited.underline() 			{ map.insert(Field::Underlined.to_str().to_owned(), Value::Bool(flags.underline())); }
+	if flags.strikethrough() != inherited.strikethrough() 	{ map.insert(Field::Strikethrough.to_str().to_owned(), Value::Bool(flags.strikethrough())); }
+	if flags.obfuscate() != inherited.obfuscate() 			{ map.insert(Field::Obfuscated.to_str().to_owned(), Value::Bool(flags.obfuscate())); }
+	
+	if style.color != parent.color {
+		map.insert(Field::Color.to_str().to_owned(), Value::String(style.color.name()));
+	}
+}
+
+fn borrowed(interaction: &Interaction) -> InteractionRef {
+	InteractionRef {
+		insert: interaction.insert.as_ref().map(|insert| insert as &str),
+		click: interaction.click.as_ref(),
+		hover: interaction.hover.as_ref()
+	}
+}
+
+/// Writes the interaction fields of `interaction` that differ from the inherited `parent` interaction. The text of `show_text` events is encoded by `hover`.
+fn write_interaction(map: &mut Map<String, Value>, interaction: InteractionRef, parent: InteractionRef, hover: fn(&ChatBuf) -> Value) {
+	if let Some(insert) = interaction.insert {
+		if interaction.insert != parent.insert {
+			map.insert(Field::Insertion.to_str().to_owned(), Value::String(insert.to_owned()));
+		}
+	}
+	
+	if let Some(click) = interaction.click {
+		if interaction.click != parent.click {
+			let mut event = Map::new();
+			event.insert(EventField::Action.to_str().to_owned(), Value::String(click.action().to_owned()));
+			event.insert(EventField::Value.to_str().to_owned(), Value::String(click.value().into_owned()));
+			
+			map.insert(Field::ClickEvent.to_str().to_owned(), Value::Object(event));
+		}
+	}
+	
+	if let Some(event) = interaction.hover {
+		if interaction.hover != parent.hover {
+			let value = match *event {
+				HoverEvent::ShowText(ref text) => hover(text),
+				_ => Value::String(event.data().unwrap_or("").to_owned())
+			};
+			
+			let mut object = Map::new();
+			object.insert(EventField::Action.to_str().to_owned(), Value::String(event.action().to_owned()));
+			object.insert(EventField::Value.to_str().to_owned(), value);
+			
+			map.insert(Field::HoverEvent.to_str().to_owned(), Value::Object(object));
+		}
+	}
+}
+
+fn faithful_hover(text: &ChatBuf) -> Value {
+	faithful_forest(&forest(text), Style::new())
+}
+
+/// Writes the payload of a component. Translation arguments are encoded by `arg`, which receives the style and interaction of the translation.
+fn write_payload<'a, F>(map: &mut Map<String, Value>, node: &Node<'a>, mut arg: F) where F: FnMut(&Node<'a>, Style, InteractionRef<'a>) -> Value {
+	let component = &node.component;
+	let text = Value::String(component.text().to_owned());
+	
+	match component.kind() {
+		Kind::Translate => {
+			map.insert(Field::Translate.to_str().to_owned(), text);
+			
+			if !node.meta.is_empty() {
+				let with = node.meta.iter().map(|child| arg(child, component.style(), node.interaction)).collect();
+				map.insert(Field::With.to_str().to_owned(), Value::Array(with));
+			}
+		},
+		Kind::ScoreName => {
+			let mut score = Map::new();
+			score.insert(ScoreField::Name.to_str().to_owned(), text);
+			score.insert(ScoreField::Objective.to_str().to_owned(), Value::String(String::new()));
+			
+			for child in &node.meta {
+				let value = Value::String(child.component.text().to_owned());
+				
+				match child.component.kind() {
+					Kind::ScoreObjective => { score.insert(ScoreField::Objective.to_str().to_owned(), value); },
+					Kind::ScoreValue => { score.insert(ScoreField::Value.to_str().to_owned(), value); },
+					_ => ()
+				}
+			}
+			
+			map.insert(Field::Score.to_str().to_owned(), Value::Object(score));
+		},
+		Kind::Selector => { map.insert(Field::Selector.to_str().to_owned(), text); },
+		Kind::Keybind => { map.insert(Field::Keybind.to_str().to_owned(), text); },
+		// Parts of a score outside of a score are written as text.
+		Kind::Text | Kind::ScoreObjective | Kind::ScoreValue => { map.insert(Field::Text.to_str().to_owned(), text); }
+	}
+}
+
+fn faithful(node: &Node, parent: Style) -> Value {
+	let style = node.component.style();
+	let mut map = Map::new();
+	
+	write_style(&mut map, style, parent);
+	
+	if let Some(own) = node.own {
+		write_interaction(&mut map, borrowed(own), InteractionRef::none(), faithful_hover);
+	}
+	
+	write_payload(&mut map, node, |child, style, _| faithful(child, style));
+	
+	if !node.extra.is_empty() {
+		let extra = node.extra.iter().map(|child| faithful(child, style)).collect();
+		map.insert(Field::Extra.to_str().to_owned(), Value::Array(extra));
+	}
+	
+	Value::Object(map)
+}
+
+/// Encodes multiple top level components as a single container. An empty parent is used so that the components do not inherit each other's style.
+fn faithful_forest(nodes: &[Node], parent: Style) -> Value {
+	match nodes.len() {
+		0 => Value::String(String::new()),
+		1 => faithful(&nodes[0], parent),
+		_ => Value::Array(
+			Some(Value::String(String::new())).into_iter()
+				.chain(nodes.iter().map(|node| faithful(node, parent)))
+				.collect()
+		)
+	}
+}
+
+/// A styled run of text, or a component that cannot be merged into a run, such as a translation.
+enum Run<'n, 'a: 'n> {
+	Text(String, Style, InteractionRef<'a>),
+	Node(&'n Node<'a>)
+}
+
+impl<'n, 'a> Run<'n, 'a> {
+	fn style(&self) -> Style {
+		match *self {
+			Run::Text(_, style, _) => style,
+			Run::Node(node) => node.component.style()
+		}
+	}
+	
+	fn interaction(&self) -> InteractionRef<'a> {
+		match *self {
+			Run::Text(_, _, interaction) => interaction,
+			Run::Node(node) => node.interaction
+		}
+	}
+}
+
+fn runs<'n, 'a>(nodes: &'n [Node<'a>], runs: &mut Vec<Run<'n, 'a>>) {
+	for node in nodes {
+		let component = &node.component;
+		
+		if component.kind() == Kind::Text {
+			let merged = match runs.last_mut() {
+				Some(&mut Run::Text(ref mut text, style, interaction)) if style == component.style() && interaction == node.interaction => { 
+					text.push_str(component.text()); 
+					true 
+				},
+				_ => false
+			};
+			
+			if !merged && !component.text().is_empty() {
+				runs.push(Run::Text(component.text().to_owned(), component.style(), node.interaction));
+			}
+		} else {
+			runs.push(Run::Node(node));
+		}
+		
+		self::runs(&node.extra, runs);
+	}
+}
+
+/// Encodes a single run. Text runs that do not change the style or interaction are encoded as bare strings.
+fn encode_run<'n, 'a, F>(run: &Run<'n, 'a>, parent: Style, inherited: InteractionRef<'a>, hover: fn(&ChatBuf) -> Value, arg: F) -> Value 
+	where F: FnMut(&Node<'a>, Style, InteractionRef<'a>) -> Value {
+	
+	match *run {
+		Run::Text(ref text, style, interaction) if style == parent && interaction == inherited => Value::String(text.clone()),
+		Run::Text(ref text, style, interaction) => {
+			let mut map = Map::new();
+			
+			write_style(&mut map, style, parent);
+			write_interaction(&mut map, interaction, inherited, hover);
+			map.insert(Field::Text.to_str().to_owned(), Value::String(text.clone()));
+			
+			Value::Object(map)
+		},
+		Run::Node(node) => {
+			let mut map = Map::new();
+			
+			write_style(&mut map, node.component.style(), parent);
+			write_interaction(&mut map, node.interaction, inherited, hover);
+			write_payload(&mut map, node, arg);
+			
+			Value::Object(map)
+		}
+	}
+}
+
+/// Encodes the runs as an array, where the first element has the style `head` and is inherited by the rest.
+/// The first element never has an interaction of its own, because it would be inherited by the rest of the elements.
+fn packed<'n, 'a, F>(runs: &[Run<'n, 'a>], head: Style, parent: Style, inherited: InteractionRef<'a>, hover: fn(&ChatBuf) -> Value, mut arg: F) -> Value 
+	where F: FnMut(&Node<'a>, Style, InteractionRef<'a>) -> Value {
+	
+	let (first, rest) = match runs.first() {
+		Some(run) if run.style() == head && run.interaction() == inherited => (encode_run(run, parent, inherited, hover, &mut arg), &runs[1..]),
+		_ => (encode_run(&Run::Text(String::new(), head, inherited), parent, inherited, hover, &mut arg), runs)
+	};
+	
+	if rest.is_empty() {
+		first
+	} else {
+		Value::Array(
+			Some(first).into_iter()
+				.chain(rest.iter().map(|run| encode_run(run, head, inherited, hover, &mut arg)))
+				.collect()
+		)
+	}
+}
+
+fn flattened_hover(text: &ChatBuf) -> Value {
+	flattened(&forest(text))
+}
+
+fn flattened(nodes: &[Node]) -> Value {
+	let mut list = Vec::new();
+	runs(nodes, &mut list);
+	
+	if list.is_empty() {
+		Value::String(String::new())
+	} else {
+		packed(&list, Style::new(), Style::new(), InteractionRef::none(), flattened_hover, |child, style, _| faithful(child, style))
+	}
+}
+
+fn encoded_len(value: &Value) -> usize {
+	serde_json::to_string(value).expect("serializing a JSON value should never fail").len()
+}
+
+fn best_fit_hover(text: &ChatBuf) -> Value {
+	best_fit(&forest(text), Style::new(), InteractionRef::none())
+}
+
+/// Tries the faithful encoding, along with a packed encoding for each distinct style, and returns the shortest one.
+fn best_fit<'a>(nodes: &[Node<'a>], parent: Style, inherited: InteractionRef<'a>) -> Value {
+	let mut list = Vec::new();
+	runs(nodes, &mut list);
+	
+	if list.is_empty() {
+		return Value::String(String::new());
+	}
+	
+	let arg = |node: &Node<'a>, style, inherited| best_fit(::std::slice::from_ref(node), style, inherited);
+	
+	let mut heads: Vec<Style> = vec![parent];
+	for run in &list {
+		if !heads.contains(&run.style()) {
+			heads.push(run.style());
+		}
+	}
+	
+	let mut best = faithful_forest(nodes, parent);
+	let mut best_len = encoded_len(&best);
+	
+	for &head in &heads {
+		let candidate = packed(&list, head, parent, inherited, best_fit_hover, &arg);
+		let len = encoded_len(&candidate);
+		
+		if len < best_len {
+			best = candidate;
+			best_len = len;
+		}
+	}
+	
+	best
+}
+
+pub fn into_flattened(buf: &ChatBuf) -> Value {
+	flattened(&forest(buf))
+}
+
+pub fn into_bestfit(buf: &ChatBuf) -> Value {
+	best_fit(&forest(buf), Style::new(), InteractionRef::none())
+}
+
+impl Serialize for ChatBuf {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		faithful_forest(&forest(self), Style::new()).serialize(serializer)
+	}
+}
+
+/// Text as displayed: the interactions are compared by their debug representation, since the text of `show_text` events may be encoded differently.
+#[cfg(test)]
+fn rendered(buf: &ChatBuf) -> Vec<(String, Kind, Style, bool, String)> {
+	let mut list: Vec<(String, Kind, Style, bool, String)> = Vec::new();
+	
+	for (index, c) in buf.components().enumerate() {
+		let interaction = rendered_interaction(buf.interaction(index));
+		
+		if c.kind() == Kind::Text && !c.meta() {
+			if c.text().is_empty() { continue }
+			
+			if let Some(last) = list.last_mut() {
+				if last.1 == Kind::Text && !last.3 && last.2 == c.style() && last.4 == interaction {
+					last.0.push_str(c.text());
+					continue;
+				}
+			}
+		}
+		
+		list.push((c.text().to_owned(), c.kind(), c.style(), c.meta(), interaction));
+	}
+	
+	list
+}
+
+#[cfg(test)]
+fn rendered_interaction(interaction: InteractionRef) -> String {
+	let hover = match interaction.hover {
+		Some(&HoverEvent::ShowText(ref text)) => format!("{:?}", rendered(text)),
+		other => format!("{:?}", other)
+	};
+	
+	format!("{:?} {:?} {}", interaction.insert, interaction.click, hover)
+}
+
+#[test]
+fn test_into_json() {
+	let source = r#"{"extra":[{"text":"[Server] "},{"bold":true,"extra":[{"bold":false,"text":"!"}],"text":"Welcome"},{"translate":"chat.type.text","with":[{"text":"Steve","color":"red"},"hi"]}],"color":"gold","text":""}"#;
+	let buf = ChatBuf::from_json(source).unwrap();
+	
+	assert_eq!(
+		buf.into_json(), 
+		r#"{"color":"gold","extra":[{"text":"[Server] "},{"bold":true,"extra":[{"bold":false,"text":"!"}],"text":"Welcome"},{"translate":"chat.type.text","with":[{"color":"red","text":"Steve"},{"text":"hi"}]}],"text":""}"#
+	);
+	
+	assert_eq!(
+		buf.into_json_flattened(), 
+		r#"["",{"color":"gold","text":"[Server] "},{"bold":true,"color":"gold","text":"Welcome"},{"color":"gold","text":"!"},{"color":"gold","translate":"chat.type.text","with":[{"color":"red","text":"Steve"},{"text":"hi"}]}]"#
+	);
+	
+	assert_eq!(
+		buf.into_json_bestfit(), 
+		r#"[{"color":"gold","text":"[Server] "},{"bold":true,"text":"Welcome"},"!",{"translate":"chat.type.text","with":[{"color":"red","text":"Steve"},"hi"]}]"#
+	);
+	
+	assert_eq!(ChatBuf::new().into_json(), r#""""#);
+	assert_eq!(ChatBuf::from_json(r#"{"text":"plain"}"#).unwrap().into_json_bestfit(), r#""plain""#);
+	
+	// A component with an interaction cannot be the head of a packed array, since the rest of the elements would inherit it.
+	let buf = ChatBuf::from_json(r#"[{"text":"a","clickEvent":{"action":"run_command","value":"/a"}},"b"]"#).unwrap();
+	
+	assert_eq!(
+		buf.into_json(), 
+		r#"{"clickEvent":{"action":"run_command","value":"/a"},"extra":[{"text":"b"}],"text":"a"}"#
+	);
+	
+	assert_eq!(
+		buf.into_json_flattened(), 
+		r#"["",{"clickEvent":{"action":"run_command","value":"/a"},"text":"ab"}]"#
+	);
+}
+
+#[test]
+fn test_into_json_equivalence() {
+	let sources = [
+		r#"["a",{"score":{"name":"@p","objective":"kills","value":"2"}},{"text":"b","color":"red"},{"text":"c","color":"red","bold":true},"d"]"#,
+		r#"{"translate":"death.attack.arrow","with":[{"text":"Steve","extra":[{"text":"!","italic":true}]},{"selector":"@e[type=Skeleton]"}],"color":"dark_red","extra":[{"keybind":"key.jump","underlined":true}]}"#,
+		r#"{"text":"","extra":[{"text":"a","color":"blue"},{"text":"b","color":"blue"},{"text":"","color":"green"},{"text":"c","color":"blue","obfuscated":true,"strikethrough":true}]}"#,
+		r##"{"text":"a","color":"#ff8000","extra":[{"text":"b","color":"#0A0B0C","bold":true},{"text":"c","color":"gold"}]}"##,
+		r#"{"text":"a","insertion":"i","clickEvent":{"action":"run_command","value":"/help"},"extra":["b",{"text":"c","hoverEvent":{"action":"show_text","value":{"text":"tip","color":"aqua"}}},{"translate":"t","with":["x",{"text":"y","insertion":"j"}]}]}"#,
+		r#"["",{"text":"a","clickEvent":{"action":"open_url","value":"https://example.com"}},"b",{"text":"c","hoverEvent":{"action":"show_item","value":"{id:\"minecraft:stone\",Count:1b}"}}]"#
+	];
+	
+	for source in sources.iter() {
+		let buf = ChatBuf::from_json(source).unwrap();
+		
+		let faithful = ChatBuf::from_json(&buf.into_json()).unwrap();
+		assert_eq!(
+			buf.components().map(|c| (c.text(), c.kind(), c.style(), c.mode(), c.meta())).collect::<Vec<_>>(),
+			faithful.components().map(|c| (c.text(), c.kind(), c.style(), c.mode(), c.meta())).collect::<Vec<_>>()
+		);
+		assert_eq!(buf.interactions().collect::<Vec<_>>(), faithful.interactions().collect::<Vec<_>>());
+		
+		let expected = rendered(&buf);
+		let bestfit = buf.into_json_bestfit();
+		
+		assert_eq!(expected, rendered(&ChatBuf::from_json(&buf.into_json_flattened()).unwrap()));
+		assert_eq!(expected, rendered(&ChatBuf::from_json(&bestfit).unwrap()));
+		assert!(bestfit.len() <= buf.into_json().len());
+	}
+}
@@ -0,0 +1,645 @@
+use serde::de::{self, Deserializer, DeserializeSeed, DeserializeOwned, Visitor, SeqAccess, MapAccess, IntoDeserializer};
+use std::fmt::{self, Formatter, Display};
+use std::error;
+
+/// Gson only limits the depth by the size of the stack, this keeps a malicious message from overflowing ours.
+const MAX_DEPTH: usize = 512;
+
+/// A feature of the input that is not valid JSON, but is accepted by Gson in lenient mode.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Leniency {
+	/// An object key without quotes: `{text: "a"}`
+	UnquotedName,
+	/// A string value without quotes: `{"text": a}`
+	UnquotedString,
+	/// A string or key in single quotes: `{'text': 'a'}`
+	SingleQuoted,
+	/// A comment starting with `//` or `#`, or enclosed in `/* */`.
+	Comment,
+	/// `=` or `=>` used instead of `:` between a key and a value.
+	NameSeparator,
+	/// `;` used instead of `,` between elements.
+	Semicolon,
+	/// A missing array element, such as in `[,"a"]` or `["a",]`, which Gson reads as `null`.
+	ImplicitNull,
+	/// The non-execute prefix `)]}'` followed by a newline at the start of the input.
+	NonExecutePrefix,
+	/// A keyword with uppercase letters, such as `TRUE` or `Null`.
+	KeywordCase,
+	/// An escape sequence that is not defined by JSON, such as `\a` or `\'`.
+	UnknownEscape,
+	/// A control character, such as a newline, that is not escaped inside of a string.
+	ControlCharacter
+}
+
+impl Leniency {
+	fn bit(&self) -> u16 {
+		match *self {
+			Leniency::UnquotedName 		=> 1,
+			Leniency::UnquotedString 	=> 2,
+			Leniency::SingleQuoted 		=> 4,
+			Leniency::Comment 			=> 8,
+			Leniency::NameSeparator 	=> 16,
+			Leniency::Semicolon 		=> 32,
+			Leniency::ImplicitNull 		=> 64,
+			Leniency::NonExecutePrefix 	=> 128,
+			Leniency::KeywordCase 		=> 256,
+			Leniency::UnknownEscape 	=> 512,
+			Leniency::ControlCharacter 	=> 1024
+		}
+	}
+	
+	fn all() -> [Leniency; 11] {
+		[
+			Leniency::UnquotedName, Leniency::UnquotedString, Leniency::SingleQuoted, Leniency::Comment,
+			Leniency::NameSeparator, Leniency::Semicolon, Leniency::ImplicitNull, Leniency::NonExecutePrefix,
+			Leniency::KeywordCase, Leniency::UnknownEscape, Leniency::ControlCharacter
+		]
+	}
+}
+
+/// The set of leniencies that were needed to read an input.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Leniencies(u16);
+
+impl Leniencies {
+	pub fn none() -> Self {
+		Leniencies(0)
+	}
+	
+	fn add(&mut self, leniency: Leniency) {
+		self.0 |= leniency.bit()
+	}
+	
+	pub fn contains(&self, leniency: Leniency) -> bool {
+		self.0 & leniency.bit() != 0
+	}
+	
+	/// Returns true if the input was not valid JSON.
+	pub fn any(&self) -> bool {
+		self.0 != 0
+	}
+	
+	pub fn iter(&self) -> Vec<Leniency> {
+		Leniency::all().iter().cloned().filter(|leniency| self.contains(*leniency)).collect()
+	}
+}
+
+#[derive(Debug)]
+pub enum Error {
+	/// The input is malformed, even in lenient mode.
+	Syntax { reason: &'static str, line: usize, column: usize },
+	/// The input was read successfully, but it does not describe a chat component.
+	Component(String)
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match *self {
+			Error::Syntax { reason, line, column } => write!(f, "{} at line {} column {}", reason, line, column),
+			Error::Component(ref message) => write!(f, "{}", message)
+		}
+	}
+}
+
+impl error::Error for Error {
+	fn description(&self) -> &str {
+		match *self {
+			Error::Syntax { reason, .. } => reason,
+			Error::Component(ref message) => message
+		}
+	}
+}
+
+impl de::Error for Error {
+	fn custom<T>(msg: T) -> Self where T: Display {
+		Error::Component(msg.to_string())
+	}
+}
+
+/// Returns whether the character may be part of an unquoted name or value, mirroring `JsonReader.isLiteral`.
+fn is_literal(c: char) -> bool {
+	match c {
+		'/' | '\\' | ';' | '#' | '=' | '{' | '}' | '[' | ']' | ':' | ',' | ' ' | '\t' | '\u{c}' | '\r' | '\n' => false,
+		_ => true
+	}
+}
+
+/// Checks whether an unquoted literal is a JSON number.
+fn is_number(literal: &str) -> bool {
+	let bytes = literal.as_bytes();
+	let mut i = 0;
+	
+	let digits = |i: &mut usize| {
+		let start = *i;
+		while *i < bytes.len() && bytes[*i].is_ascii_digit() { *i += 1 }
+		*i - start
+	};
+	
+	if i < bytes.len() && bytes[i] == b'-' { i += 1 }
+	
+	let integer = digits(&mut i);
+	if integer == 0 || (integer > 1 && bytes[i - integer] == b'0') {
+		// Leading zeroes are not allowed, since they could be octal.
+		return false;
+	}
+	
+	if i < bytes.len() && bytes[i] == b'.' {
+		i += 1;
+		if digits(&mut i) == 0 { return false }
+	}
+	
+	if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+		i += 1;
+		if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') { i += 1 }
+		if digits(&mut i) == 0 { return false }
+	}
+	
+	i == bytes.len()
+}
+
+/// Matches a keyword in the same way as `JsonReader.peekKeyword`, where each letter may be lowercase or uppercase.
+fn keyword(literal: &str, keyword: &str) -> Option<bool> {
+	if literal.len() != keyword.len() || !literal.chars().zip(keyword.chars()).all(|(c, k)| c == k || c == k.to_ascii_uppercase()) {
+		None
+	} else {
+		Some(literal != keyword)
+	}
+}
+
+pub struct Reader<'a> {
+	input: &'a str,
+	pos: usize,
+	line: usize,
+	line_start: usize,
+	depth: usize,
+	leniencies: Leniencies
+}
+
+impl<'a> Reader<'a> {
+	pub fn new(input: &'a str) -> Self {
+		Reader {
+			input: input,
+			pos: 0,
+			line: 1,
+			line_start: 0,
+			depth: 0,
+			leniencies: Leniencies::none()
+		}
+	}
+	
+	pub fn leniencies(&self) -> Leniencies {
+		self.leniencies
+	}
+	
+	fn error(&self, reason: &'static str) -> Error {
+		Error::Syntax {
+			reason: reason,
+			line: self.line,
+			column: self.input[self.line_start..self.pos].chars().count() + 1
+		}
+	}
+	
+	fn peek_char(&self) -> Option<char> {
+		self.input[self.pos..].chars().next()
+	}
+	
+	fn next_char(&mut self) -> Option<char> {
+		let c = self.peek_char();
+		
+		if let Some(c) = c {
+			self.pos += c.len_utf8();
+			
+			if c == '\n' {
+				self.line += 1;
+				self.line_start = self.pos;
+			}
+		}
+		
+		c
+	}
+	
+	fn skip_line(&mut self) {
+		while let Some(c) = self.next_char() {
+			if c == '\n' { break }
+		}
+	}
+	
+	/// Skips whitespace and comments, and returns the next character without consuming it.
+	fn peek_non_whitespace(&mut self) -> Result<Option<char>, Error> {
+		loop {
+			match self.peek_char() {
+				Some(' ') | Some('\t') | Some('\r') | Some('\n') => { self.next_char(); },
+				Some('#') => {
+					self.leniencies.add(Leniency::Comment);
+					self.skip_line();
+				},
+				Some('/') => {
+					match self.input[self.pos + 1..].chars().next() {
+						Some('/') => {
+							self.leniencies.add(Leniency::Comment);
+							self.skip_line();
+						},
+						Some('*') => {
+							self.leniencies.add(Leniency::Comment);
+							self.next_char();
+							self.next_char();
+							
+							loop {
+								if self.input[self.pos..].starts_with("*/") {
+									self.next_char();
+									self.next_char();
+									break;
+								}
+								
+								if self.next_char().is_none() {
+									return Err(self.error("unterminated comment"));
+								}
+							}
+						},
+						_ => return Ok(Some('/'))
+					}
+				},
+				other => return Ok(other)
+			}
+		}
+	}
+	
+	/// Reads the rest of a string started by the quote character `quote`.
+	fn read_quoted(&mut self, quote: char) -> Result<String, Error> {
+		let mut string = String::new();
+		
+		loop {
+			let c = match self.next_char() {
+				Some(c) => c,
+				None => return Err(self.error("unterminated string"))
+			};
+			
+			match c {
+				c if c == quote => return Ok(string),
+				'\\' => {
+					let escaped = self.next_char().ok_or_else(|| self.error("unterminated escape sequence"))?;
+					
+					match escaped {
+						'u' => {
+							let unit = self.read_unit()?;
+							
+							if unit >= 0xD800 && unit < 0xDC00 && self.input[self.pos..].starts_with("\\u") {
+								// Java strings are UTF-16, so characters outside of the BMP are escaped as surrogate pairs.
+								let save = (self.pos, self.line, self.line_start);
+								self.next_char();
+								self.next_char();
+								
+								let low = self.read_unit()?;
+								
+								if low >= 0xDC00 && low < 0xE000 {
+									let value = 0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00);
+									string.push(::std::char::from_u32(value).unwrap_or('\u{FFFD}'));
+								} else {
+									string.push('\u{FFFD}');
+									
+									self.pos = save.0;
+									self.line = save.1;
+									self.line_start = save.2;
+								}
+							} else {
+								string.push(::std::char::from_u32(unit).unwrap_or('\u{FFFD}'));
+							}
+						},
+						't' => string.push('\t'),
+						'b' => string.push('\u{8}'),
+						'n' => string.push('\n'),
+						'r' => string.push('\r'),
+						'f' => string.push('\u{c}'),
+						'"' | '\\' | '/' => string.push(escaped),
+						other => {
+							self.leniencies.add(Leniency::UnknownEscape);
+							string.push(other)
+						}
+					}
+				},
+				c if c < ' ' => {
+					self.leniencies.add(Leniency::ControlCharacter);
+					string.push(c)
+				},
+				c => string.push(c)
+			}
+		}
+	}
+	
+	/// Reads the 4 hex digits of a `\u` escape.
+	fn read_unit(&mut self) -> Result<u32, Error> {
+		let digits = self.input.get(self.pos..self.pos + 4).ok_or_else(|| self.error("unterminated escape sequence"))?;
+		
+		// Checked first, as from_str_radix would also accept a sign such as "+041".
+		if !digits.chars().all(|c| c.is_digit(16)) {
+			return Err(self.error("\\u must be followed by 4 hex digits"));
+		}
+		
+		let unit = u32::from_str_radix(digits, 16).expect("4 hex digits always fit in a u32");
+		
+		self.pos += 4;
+		
+		Ok(unit)
+	}
+	
+	fn read_literal(&mut self) -> &'a str {
+		let start = self.pos;
+		
+		while let Some(c) = self.peek_char() {
+			if !is_literal(c) { break }
+			self.next_char();
+		}
+		
+		&self.input[start..self.pos]
+	}
+	
+	fn enter(&mut self) -> Result<(), Error> {
+		self.depth += 1;
+		
+		if self.depth > MAX_DEPTH {
+			Err(self.error("too deeply nested"))
+		} else {
+			Ok(())
+		}
+	}
+	
+	/// Reads an entire document, which must contain exactly one value.
+	pub fn document<T>(&mut self) -> Result<T, Error> where T: DeserializeOwned {
+		self.peek_non_whitespace()?;
+		
+		if self.input[self.pos..].starts_with(")]}'\n") {
+			self.leniencies.add(Leniency::NonExecutePrefix);
+			
+			for _ in 0..5 { self.next_char(); }
+		}
+		
+		if self.peek_non_whitespace()?.is_none() {
+			return Err(self.error("end of input"));
+		}
+		
+		let value = T::deserialize(&mut *self)?;
+		
+		// Gson checks that the document was fully consumed in strict mode, so even comments are rejected here.
+		while let Some(c) = self.peek_char() {
+			match c {
+				' ' | '\t' | '\r' | '\n' => { self.next_char(); },
+				_ => return Err(self.error("JSON document was not fully consumed"))
+			}
+		}
+		
+		Ok(value)
+	}
+}
+
+impl<'de, 'r, 'a> Deserializer<'de> for &'r mut Reader<'a> {
+	type Error = Error;
+	
+	fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error> where V: Visitor<'de> {
+		match self.peek_non_whitespace()? {
+			Some('{') => {
+				self.next_char();
+				self.enter()?;
+				
+				let depth = self.depth;
+				let value = visitor.visit_map(Entries { reader: &mut *self, first: true })?;
+				
+				if self.depth != depth - 1 {
+					return Err(self.error("object was not fully consumed"));
+				}
+				
+				Ok(value)
+			},
+			Some('[') => {
+				self.next_char();
+				self.enter()?;
+				
+				let depth = self.depth;
+				let value = visitor.visit_seq(Elements { reader: &mut *self, first: true })?;
+				
+				if self.depth != depth - 1 {
+					return Err(self.error("array was not fully consumed"));
+				}
+				
+				Ok(value)
+			},
+			Some('"') => {
+				self.next_char();
+				visitor.visit_string(self.read_quoted('"')?)
+			},
+			Some('\'') => {
+				self.next_char();
+				self.leniencies.add(Leniency::SingleQuoted);
+				visitor.visit_string(self.read_quoted('\'')?)
+			},
+			Some(_) => {
+				let literal = self.read_literal();
+				
+				if literal.is_empty() {
+					return Err(self.error("expected value"));
+				}
+				
+				let (value, upper) = if let Some(upper) = keyword(literal, "true") {
+					(Some(true), upper)
+				} else if let Some(upper) = keyword(literal, "false") {
+					(Some(false), upper)
+				} else if let Some(upper) = keyword(literal, "null") {
+					(None, upper)
+				} else if is_number(literal) {
+					// Gson keeps the original text of numbers, except for integers that fit in a long.
+					return match literal.parse::<i64>() {
+						Ok(integer) => visitor.visit_string(integer.to_string()),
+						Err(_) => visitor.visit_str(literal)
+					};
+				} else {
+					self.leniencies.add(Leniency::UnquotedString);
+					return visitor.visit_str(literal);
+				};
+				
+				if upper {
+					self.leniencies.add(Leniency::KeywordCase);
+				}
+				
+				match value {
+					Some(value) => visitor.visit_bool(value),
+					None => visitor.visit_unit()
+				}
+			},
+			None => Err(self.error("end of input"))
+		}
+	}
+	
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf option unit unit_struct newtype_struct seq tuple
+		tuple_struct map struct enum identifier ignored_any
+	}
+}
+
+struct Elements<'r, 'a: 'r> {
+	reader: &'r mut Reader<'a>,
+	first: bool
+}
+
+impl<'de, 'r, 'a> SeqAccess<'de> for Elements<'r, 'a> {
+	type Error = Error;
+	
+	fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error> where T: DeserializeSeed<'de> {
+		let reader = &mut *self.reader;
+		
+		if !self.first {
+			match reader.peek_non_whitespace()? {
+				Some(']') => {
+					reader.next_char();
+					reader.depth -= 1;
+					return Ok(None);
+				},
+				Some(';') => { reader.next_char(); reader.leniencies.add(Leniency::Semicolon); },
+				Some(',') => { reader.next_char(); },
+				_ => return Err(reader.error("unterminated array"))
+			}
+		}
+		
+		let first = self.first;
+		self.first = false;
+		
+		match reader.peek_non_whitespace()? {
+			Some(']') if first => {
+				reader.next_char();
+				reader.depth -= 1;
+				
+				Ok(None)
+			},
+			Some(']') | Some(',') | Some(';') => {
+				// The separator is left for the next element, and an empty element is read as null.
+				reader.leniencies.add(Leniency::ImplicitNull);
+				seed.deserialize(().into_deserializer()).map(Some)
+			},
+			_ => seed.deserialize(&mut *reader).map(Some)
+		}
+	}
+}
+
+struct Entries<'r, 'a: 'r> {
+	reader: &'r mut Reader<'a>,
+	first: bool
+}
+
+impl<'de, 'r, 'a> MapAccess<'de> for Entries<'r, 'a> {
+	type Error = Error;
+	
+	fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error> where K: DeserializeSeed<'de> {
+		let reader = &mut *self.reader;
+		
+		if !self.first {
+			match reader.peek_non_whitespace()? {
+				Some('}') => {
+					reader.next_char();
+					reader.depth -= 1;
+					return Ok(None);
+				},
+				Some(';') => { reader.next_char(); reader.leniencies.add(Leniency::Semicolon); },
+				Some(',') => { reader.next_char(); },
+				_ => return Err(reader.error("unterminated object"))
+			}
+		}
+		
+		let first = self.first;
+		self.first = false;
+		
+		let name = match reader.peek_non_whitespace()? {
+			Some('}') if first => {
+				reader.next_char();
+				reader.depth -= 1;
+				
+				return Ok(None);
+			},
+			Some('"') => {
+				reader.next_char();
+				reader.read_quoted('"')?
+			},
+			Some('\'') => {
+				reader.next_char();
+				reader.leniencies.add(Leniency::SingleQuoted);
+				reader.read_quoted('\'')?
+			},
+			_ => {
+				let literal = reader.read_literal();
+				
+				if literal.is_empty() {
+					return Err(reader.error("expected name"));
+				}
+				
+				reader.leniencies.add(Leniency::UnquotedName);
+				literal.to_owned()
+			}
+		};
+		
+		match reader.peek_non_whitespace()? {
+			Some(':') => { reader.next_char(); },
+			Some('=') => {
+				reader.next_char();
+				reader.leniencies.add(Leniency::NameSeparator);
+				
+				if reader.peek_char() == Some('>') {
+					reader.next_char();
+				}
+			},
+			_ => return Err(reader.error("expected ':'"))
+		}
+		
+		seed.deserialize(name.into_deserializer()).map(Some)
+	}
+	
+	fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error> where V: DeserializeSeed<'de> {
+		seed.deserialize(&mut *self.reader)
+	}
+}
+
+#[test]
+fn test_lenient_chat() {
+	use text::repr::flat::{ChatBuf, Kind};
+	use text::style::{Color, PaletteColor};
+	
+	let (buf, leniencies) = ChatBuf::from_json_lenient("{text:'Hello ', color: gold; bold=>TRUE, /* old plugin */ extra:[world, 1.50, -0] # trailing\n}").unwrap();
+	let components: Vec<_> = buf.components().collect();
+	
+	assert_eq!(components.iter().map(|c| c.text()).collect::<Vec<_>>(), vec!["Hello ", "world", "1.50", "0"]);
+	assert!(components.iter().all(|c| c.kind() == Kind::Text && c.style().flags.bold() && c.style().color == Color::Palette(PaletteColor::Gold)));
+	
+	assert_eq!(leniencies.iter(), vec![
+		Leniency::UnquotedName, Leniency::UnquotedString, Leniency::SingleQuoted, Leniency::Comment, 
+		Leniency::NameSeparator, Leniency::Semicolon, Leniency::KeywordCase
+	]);
+	
+	let (buf, leniencies) = ChatBuf::from_json_lenient(")]}'\n\"a\\qb\"").unwrap();
+	assert_eq!(buf.components().next().unwrap().text(), "aqb");
+	assert_eq!(leniencies.iter(), vec![Leniency::NonExecutePrefix, Leniency::UnknownEscape]);
+	
+	let (buf, leniencies) = ChatBuf::from_json_lenient(r#"{"text":"😀 strict"}"#).unwrap();
+	assert_eq!(buf.components().next().unwrap().text(), "\u{1F600} strict");
+	assert!(!leniencies.any());
+}
+
+#[test]
+fn test_lenient_errors() {
+	use text::repr::flat::ChatBuf;
+	
+	// Gson reads the missing element as null, which is not a component.
+	match ChatBuf::from_json_lenient(r#"["a",]"#) {
+		Err(Error::Component(_)) => (),
+		other => panic!("unexpected result: {:?}", other.map(|(_, leniencies)| leniencies))
+	}
+	
+	let syntax = |json: &str| match ChatBuf::from_json_lenient(json) {
+		Err(Error::Syntax { reason, line, column }) => (reason, line, column),
+		other => panic!("unexpected result: {:?}", other.map(|(_, leniencies)| leniencies))
+	};
+	
+	assert_eq!(syntax(r#"{"text":"a",}"#), ("expected name", 1, 13));
+	assert_eq!(syntax("\"a\" // comment"), ("JSON document was not fully consumed", 1, 5));
+	assert_eq!(syntax("{\"text\":\n\"abc"), ("unterminated string", 2, 5));
+	assert_eq!(syntax("  "), ("end of input", 1, 3));
+	assert_eq!(syntax("{\"text\" \"a\"}"), ("expected ':'", 1, 9));
+	assert_eq!(syntax(r#""\u+041""#), ("\\u must be followed by 4 hex digits", 1, 4));
+}
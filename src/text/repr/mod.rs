@@ -1,4 +1,9 @@
 pub mod plain;
 pub mod flat;
+mod flat_deserialize;
+mod flat_serialize;
+/// A JSON reader accepting the same malformed JSON as Gson's `JsonReader` in lenient mode.
+/// Minecraft parsed chat with `Gson.fromJson` before 1.12, which silently enables lenient mode.
+pub mod lenient;
 /// Reference implementation of ChatComponents. Fairly inefficient.
-mod reference;
\ No newline at end of file
+pub mod reference;
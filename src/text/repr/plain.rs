@@ -5,12 +5,14 @@ use serde::de::{Deserializer, Deserialize, Error, Visitor};
 use serde::{Serializer, Serialize};
 use std::iter::Peekable;
 use std::slice;
+use std::ops::Range;
+use std::cmp;
 
-// [PlainBuf] Overhead: 48 bytes for collections, 4 bytes per descriptor, random access
+// [PlainBuf] Overhead: 48 bytes for collections, 6 bytes per descriptor, random access
 // [Encoded] Overhead: 24 bytes for collections, 2 to 12 bytes per descriptor
 
 /// A buffer storing an unstyled string annotated with styles in a descriptor buffer.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PlainBuf {
 	/// The unstyled string
 	string: String,
@@ -88,7 +90,172 @@ impl PlainBuf {
 		}
 	}
 	
-	// TODO: Pop, Truncate
+	/// Removes the last character, returning it along with its style.
+	pub fn pop(&mut self) -> Option<(char, Style)> {
+		let c = self.string.pop()?;
+		
+		let remove = {
+			let last = self.descriptors.last_mut().expect("a PlainBuf with text should have descriptors");
+			last.0 -= utf8_len(c) as u8;
+			
+			last.0 == 0
+		};
+		
+		let style = if remove {
+			self.descriptors.pop().unwrap().1
+		} else {
+			self.descriptors[self.descriptors.len() - 1].1
+		};
+		
+		Some((c, style))
+	}
+	
+	/// Shortens the buffer to `len` bytes. Does nothing if the buffer is already shorter. Panics if `len` is not on a character boundary, like `String::truncate`.
+	pub fn truncate(&mut self, len: usize) {
+		if len >= self.string.len() {
+			return;
+		}
+		
+		assert!(self.string.is_char_boundary(len), "new PlainBuf length must be on a character boundary");
+		
+		let mut start = 0;
+		let mut kept = 0;
+		
+		for descriptor in &mut self.descriptors {
+			if start >= len {
+				break;
+			}
+			
+			descriptor.0 = cmp::min(descriptor.0 as usize, len - start) as u8;
+			start += descriptor.0 as usize;
+			kept += 1;
+		}
+		
+		self.string.truncate(len);
+		self.descriptors.truncate(kept);
+	}
+	
+	/// Shortens the buffer to `chars` characters. Does nothing if the buffer is already shorter.
+	pub fn truncate_chars(&mut self, chars: usize) {
+		if let Some((len, _)) = self.string.char_indices().nth(chars) {
+			self.truncate(len);
+		}
+	}
+	
+	/// Copies a byte range of the buffer into a new buffer, keeping the styles. Panics if the range is not on character boundaries, like slicing a `str`.
+	pub fn slice(&self, range: Range<usize>) -> PlainBuf {
+		let mut buf = PlainBuf::with_capacity(range.end - range.start, 0);
+		let mut start = 0;
+		
+		// Check the boundaries before splitting up the string.
+		let _ = &self.string[range.clone()];
+		
+		for &(len, style) in &self.descriptors {
+			let end = start + len as usize;
+			let (from, to) = (cmp::max(start, range.start), cmp::min(end, range.end));
+			
+			if from < to {
+				buf.push(&self.string[from..to], style);
+			}
+			
+			start = end;
+		}
+		
+		buf
+	}
+	
+	/// Appends a copy of another buffer to the end of this one.
+	pub fn append(&mut self, other: &PlainBuf) {
+		self.string.push_str(&other.string);
+		self.descriptors.extend_from_slice(&other.descriptors);
+	}
+	
+	/// Changes the styles of the buffer without changing how it looks, so that it is written with the fewest § codes.
+	/// Runs of spaces may take the style of the text around them when the difference would not be visible, which skips the codes before or after them.
+	pub fn optimize(&mut self) {
+		let runs = self.iter().collect::<Vec<_>>();
+		let spaces = |index: usize| runs[index].0.chars().all(|c| c == ' ');
+		
+		// Each run of spaces may take its own style, or the style of any run in the same group of spaces or next to it.
+		let candidates = (0..runs.len()).map(|index| {
+			let own = runs[index].1;
+			
+			if !spaces(index) {
+				return vec![own];
+			}
+			
+			let mut start = index;
+			let mut end = index + 1;
+			
+			while start > 0 && spaces(start - 1) { start -= 1; }
+			while end < runs.len() && spaces(end) { end += 1; }
+			
+			let before = if start == 0 { Style::new() } else { runs[start - 1].1 };
+			let after = runs.get(end).map(|&(_, style)| style).unwrap_or(own);
+			
+			let mut styles = vec![own];
+			
+			for style in runs[start..end].iter().map(|&(_, style)| style).chain(Some(before)).chain(Some(after)) {
+				if same_on_spaces(own, style) && !styles.contains(&style) {
+					styles.push(style);
+				}
+			}
+			
+			styles
+		}).collect::<Vec<_>>();
+		
+		// The amount of codes written between two runs, after RGB colors are replaced by palette colors.
+		let cost = |from: Style, to: Style| from.to_legacy().transition(to.to_legacy()).count();
+		
+		// Finds the cheapest choice of styles, run by run. Each entry holds the total cost and the choice for the previous run.
+		let mut totals: Vec<Vec<(usize, usize)>> = Vec::with_capacity(runs.len());
+		
+		for (index, styles) in candidates.iter().enumerate() {
+			let row = styles.iter().map(|&style| {
+				if index == 0 {
+					(cost(Style::new(), style), 0)
+				} else {
+					candidates[index - 1].iter().zip(&totals[index - 1]).enumerate()
+						.map(|(choice, (&previous, &(total, _)))| (total + cost(previous, style), choice))
+						.min_by_key(|&(total, _)| total)
+						.unwrap()
+				}
+			}).collect();
+			
+			totals.push(row);
+		}
+		
+		let mut chosen = vec![Style::new(); runs.len()];
+		
+		if let Some(last) = totals.last() {
+			let mut choice = (0..last.len()).min_by_key(|&choice| last[choice].0).unwrap();
+			
+			for index in (0..runs.len()).rev() {
+				chosen[index] = candidates[index][choice];
+				choice = totals[index][choice].1;
+			}
+		}
+		
+		let mut optimized = PlainBuf::with_capacity(self.string.len(), self.descriptors.len());
+		
+		for (&(part, _), &style) in runs.iter().zip(&chosen) {
+			optimized.push(part, style);
+		}
+		
+		*self = optimized;
+	}
+}
+
+/// Returns true if spaces look the same in both styles. Spaces have no glyph, so only the bold flag changes how wide they are.
+/// Underlines and strikethroughs are drawn over spaces, and obfuscated spaces are replaced with random characters, so those also need the same color.
+fn same_on_spaces(a: Style, b: Style) -> bool {
+	let visible = |style: Style| style.flags.underline() || style.flags.strikethrough() || style.flags.obfuscate();
+	
+	a.flags.bold() == b.flags.bold()
+		&& a.flags.underline() == b.flags.underline()
+		&& a.flags.strikethrough() == b.flags.strikethrough()
+		&& a.flags.obfuscate() == b.flags.obfuscate()
+		&& (!visible(a) || a.color == b.color)
 }
 
 impl FromStr for PlainBuf {
@@ -263,7 +430,10 @@ impl<'w, W> FormatWriter<'w, W> where W: fmt::Write {
 		FormatWriter { target, current_style: Style::new(), marker }
 	}
 	
+	/// Writes the string in the style. RGB colors are written as the nearest palette color.
 	pub fn write(&mut self, string: &str, style: Style) -> fmt::Result {
+		let style = style.to_legacy();
+		
 		for command in self.current_style.transition(style) {
 			self.target.write_char(self.marker)?;
 			self.target.write_char(command.as_code())?;
@@ -299,4 +469,71 @@ impl Serialize for PlainBuf {
     {
         serializer.serialize_str(&self.to_string())
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_plain_edit() {
+	let mut buf = "§cab§lcé§rd".parse::<PlainBuf>().unwrap();
+	let runs = buf.iter().map(|(_, style)| style).collect::<Vec<_>>();
+	let (red, bold) = (runs[0], runs[1]);
+	
+	assert_eq!(buf.pop(), Some(('d', Style::new())));
+	assert_eq!(buf.iter().collect::<Vec<_>>(), vec![("ab", red), ("cé", bold)]);
+	assert_eq!(buf.pop(), Some(('é', bold)));
+	assert_eq!(buf.iter().collect::<Vec<_>>(), vec![("ab", red), ("c", bold)]);
+	
+	let mut buf = "§cab§lcé§rd".parse::<PlainBuf>().unwrap();
+	assert_eq!(buf.slice(1..5).iter().collect::<Vec<_>>(), vec![("b", red), ("cé", bold)]);
+	assert_eq!(buf.slice(2..2).iter().next(), None);
+	
+	buf.truncate_chars(3);
+	assert_eq!(buf.iter().collect::<Vec<_>>(), vec![("ab", red), ("c", bold)]);
+	buf.truncate(1);
+	assert_eq!(buf.iter().collect::<Vec<_>>(), vec![("a", red)]);
+	
+	buf.append(&"x".parse::<PlainBuf>().unwrap());
+	assert_eq!(buf.iter().collect::<Vec<_>>(), vec![("a", red), ("x", Style::new())]);
+}
+
+#[test]
+fn test_plain_write() {
+	use text::style::StyleFlags;
+	
+	let encode = |s: &str| s.parse::<PlainBuf>().unwrap().to_string();
+	let optimize = |s: &str| {
+		let mut buf = s.parse::<PlainBuf>().unwrap();
+		buf.optimize();
+		buf.to_string()
+	};
+	
+	assert_eq!(encode("§lA§l§nB"), "§lA§nB");
+	assert_eq!(encode("§c§l§m§o§k§nA"), "§c§l§n§o§m§kA");
+	assert_eq!(encode("§l§oA§cB§rC"), "§l§oA§cB§rC");
+	assert_eq!(encode("§lA§r§nB"), "§lA§r§nB");
+	assert_eq!(encode("§fA§f§rB"), "§fA§rB");
+	
+	// Every transition is already as short as possible, so only the styles of spaces can be improved.
+	assert_eq!(optimize("§cRed§r §cText"), "§cRed Text");
+	assert_eq!(optimize("§lA§r§o§l §r§lB"), "§lA B");
+	assert_eq!(optimize("§cA§r§n §cB"), "§cA§r§n §cB");
+	assert_eq!(optimize("§lA§r B"), "§lA§r B");
+	assert_eq!(optimize("§c §rA"), " A");
+	assert_eq!(optimize("§nA§c§n B"), "§nA§c§n B");
+	assert_eq!(optimize("§lA§r §cB"), "§lA§c B");
+	assert_eq!(optimize("§lA§r §o §cB"), "§lA§c  B");
+	assert_eq!(optimize("A§c §r"), "A ");
+	
+	let mut buf = "§cA§r §cB".parse::<PlainBuf>().unwrap();
+	buf.optimize();
+	assert_eq!(buf.unstyled(), "A B");
+	
+	// RGB colors are written as the nearest palette color, and colors that are the same after that are not written again.
+	let rgb = |r, g, b| Style { color: Color::Rgb(r, g, b), flags: StyleFlags::none() };
+	let mut buf = PlainBuf::new();
+	buf.push("a", rgb(0xFF, 0x80, 0x00));
+	buf.push("b", rgb(0xFF, 0xAA, 0x10));
+	buf.push("c", rgb(0x50, 0x50, 0x50));
+	buf.push("d", rgb(0xFF, 0xFF, 0xFF));
+	
+	assert_eq!(buf.to_string(), "§6ab§8c§fd");
+}
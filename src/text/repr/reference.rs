@@ -1,5 +1,14 @@
 use text::style::Style;
+use text::repr::flat::{self, ChatBuf, Kind, Mode, MAX_NESTING};
+use text::repr::flat_deserialize::{Field, EventField, ScoreField, StringLike, BoolLike, ColorName};
+use text::repr::flat_serialize::write_style;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+use serde_json::{self, Value, Map};
+use std::borrow::Cow;
+use std::ops::Range;
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum Container {
 	/// A single component.
 	Single { component: Component, extra: Option<Vec<Container>> },
@@ -9,20 +18,40 @@ pub enum Container {
 	Primitive(String)
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Component {
 	segment: Segment,
 	interact: Interaction
 }
 
+/// Styles are stored fully resolved, like in ChatBuf, instead of only the properties that were specified.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Segment {
 	style: Style,
 	payload: Payload
 }
 
-pub struct Interaction {
-	insert: Option<String>,
-	click: Option<ClickEvent>,
-	hover: Option<HoverEvent>
+/// The interaction of a component. These are inherited by the children of the component, each field seperately.
+/// The flat representation stores the text of `show_text` hover events in a ChatBuf instead of a Container.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interaction<T = Box<Container>> {
+	pub insert: Option<String>,
+	pub click: Option<ClickEvent>,
+	pub hover: Option<HoverEvent<T>>
+}
+
+impl<T> Interaction<T> {
+	pub fn none() -> Self {
+		Interaction {
+			insert: None,
+			click: None,
+			hover: None
+		}
+	}
+	
+	pub fn is_none(&self) -> bool {
+		self.insert.is_none() && self.click.is_none() && self.hover.is_none()
+	}
 }
 
 // When decoding, try to decode in the following order:
@@ -33,14 +62,17 @@ pub struct Interaction {
 // Keybind
 // (return an error)
 
-enum Payload {
+#[derive(Debug, Clone, PartialEq)]
+pub enum Payload {
 	Text(String),
 	Translation { translate: String, with: Vec<Container> },
 	Score { name: String, objective: String, value: Option<String> },
-	Selector(String)
+	Selector(String),
+	Keybind(String)
 }
 
-enum ClickEvent {
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClickEvent {
 	/// Same as OpenFile, but protocol must be http/https.
 	OpenUrl(String),
 	/// The string is actually a URL. Any protocol is accepted.
@@ -56,8 +88,9 @@ enum ClickEvent {
 	Unsupported { action: String, value: String }
 }
 
-enum HoverEvent {
-	ShowText(Box<Container>),
+#[derive(Debug, Clone, PartialEq)]
+pub enum HoverEvent<T = Box<Container>> {
+	ShowText(T),
 	/// ItemStack NBT data in Mojangson format.
 	ShowItem(String),
 	/// Entity NBT data in Mojangson format. Only uses 3 values: id (Entity UUID), type (string, minecraft:whatever), and name (the entity's custom name).
@@ -65,4 +98,717 @@ enum HoverEvent {
 	/// String is the id of the achievement.
 	ShowAchievement(String),
 	Unsupported { action: String, value: String }
-}
\ No newline at end of file
+}
+
+impl ClickEvent {
+	/// Creates a click event from the `action` and `value` fields of a JSON `clickEvent`. Unknown actions are kept as unsupported events.
+	pub fn new(action: &str, value: String) -> Self {
+		match action {
+			"open_url" 			=> ClickEvent::OpenUrl(value),
+			"open_file" 		=> ClickEvent::OpenFile(value),
+			"run_command" 		=> ClickEvent::RunCommand(value),
+			"twitch_user_info" 	=> ClickEvent::TwitchUserInfo(value),
+			"suggest_command" 	=> ClickEvent::SuggestCommand(value),
+			"change_page" 		=> match value.parse::<f64>() {
+				Ok(page) => ClickEvent::ChangePage(page),
+				Err(_) => ClickEvent::Unsupported { action: action.to_owned(), value: value }
+			},
+			_ => ClickEvent::Unsupported { action: action.to_owned(), value: value }
+		}
+	}
+	
+	pub fn action(&self) -> &str {
+		match *self {
+			ClickEvent::OpenUrl(_) 			=> "open_url",
+			ClickEvent::OpenFile(_) 		=> "open_file",
+			ClickEvent::RunCommand(_) 		=> "run_command",
+			ClickEvent::TwitchUserInfo(_) 	=> "twitch_user_info",
+			ClickEvent::SuggestCommand(_) 	=> "suggest_command",
+			ClickEvent::ChangePage(_) 		=> "change_page",
+			ClickEvent::Unsupported { ref action, .. } => action
+		}
+	}
+	
+	pub fn value(&self) -> Cow<str> {
+		match *self {
+			ClickEvent::OpenUrl(ref value) | 
+			ClickEvent::OpenFile(ref value) | 
+			ClickEvent::RunCommand(ref value) | 
+			ClickEvent::TwitchUserInfo(ref value) | 
+			ClickEvent::SuggestCommand(ref value) |
+			ClickEvent::Unsupported { ref value, .. } => Cow::Borrowed(value),
+			ClickEvent::ChangePage(page) => if page.fract() == 0.0 && page.abs() < 1e15 {
+				Cow::Owned(format!("{}", page as i64))
+			} else {
+				Cow::Owned(format!("{}", page))
+			}
+		}
+	}
+}
+
+impl<T> HoverEvent<T> {
+	/// Creates a hover event for any action other than `show_text`, which store the unformatted text of the value instead.
+	pub fn from_data(action: &str, data: String) -> Self {
+		match action {
+			"show_item" 		=> HoverEvent::ShowItem(data),
+			"show_entity" 		=> HoverEvent::ShowEntity(data),
+			"show_achievement" 	=> HoverEvent::ShowAchievement(data),
+			_ => HoverEvent::Unsupported { action: action.to_owned(), value: data }
+		}
+	}
+	
+	pub fn action(&self) -> &str {
+		match *self {
+			HoverEvent::ShowText(_) 		=> "show_text",
+			HoverEvent::ShowItem(_) 		=> "show_item",
+			HoverEvent::ShowEntity(_) 		=> "show_entity",
+			HoverEvent::ShowAchievement(_) 	=> "show_achievement",
+			HoverEvent::Unsupported { ref action, .. } => action
+		}
+	}
+	
+	/// Returns the data of the event, or None for `show_text` events.
+	pub fn data(&self) -> Option<&str> {
+		match *self {
+			HoverEvent::ShowText(_) => None,
+			HoverEvent::ShowItem(ref data) | 
+			HoverEvent::ShowEntity(ref data) | 
+			HoverEvent::ShowAchievement(ref data) |
+			HoverEvent::Unsupported { value: ref data, .. } => Some(data)
+		}
+	}
+}
+
+impl Container {
+	pub fn single(component: Component) -> Self {
+		Container::Single { component: component, extra: None }
+	}
+	
+	/// Creates a component with children. An empty list of children is stored as None, since `"extra":[]` is not valid.
+	pub fn with_extra(component: Component, extra: Vec<Container>) -> Self {
+		Container::Single { component: component, extra: if extra.is_empty() { None } else { Some(extra) } }
+	}
+	
+	/// Parses a JSON chat component into a tree. This follows the same rules as `ChatBuf::from_json`.
+	pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+		serde_json::from_str(json)
+	}
+	
+	/// Encodes the container as JSON. Only style properties that differ from the parent are written.
+	pub fn into_json(&self) -> String {
+		serde_json::to_string(self).expect("serializing a Container should never fail")
+	}
+	
+	/// Returns the style that the children of the container inherit, given the style of the parent.
+	pub fn style(&self, parent: Style) -> Style {
+		match *self {
+			Container::Single { ref component, .. } => component.segment.style,
+			Container::Array(ref containers) => containers.first().map(|first| first.style(parent)).unwrap_or(parent),
+			Container::Primitive(_) => parent
+		}
+	}
+	
+	/// Returns the text of all text components and primitives in the container, including translation arguments. 
+	/// This is used as the data of hover events other than `show_text`.
+	pub fn unformatted(&self) -> String {
+		let mut target = String::new();
+		self.write_unformatted(&mut target);
+		
+		target
+	}
+	
+	fn write_unformatted(&self, target: &mut String) {
+		match *self {
+			Container::Single { ref component, ref extra } => {
+				match component.segment.payload {
+					Payload::Text(ref text) => target.push_str(text),
+					Payload::Translation { ref with, .. } => for arg in with { arg.write_unformatted(target) },
+					_ => ()
+				}
+				
+				for child in extra.iter().flat_map(|extra| extra.iter()) {
+					child.write_unformatted(target);
+				}
+			},
+			Container::Array(ref containers) => for container in containers { container.write_unformatted(target) },
+			Container::Primitive(ref text) => target.push_str(text)
+		}
+	}
+	
+	/// Converts the container into a flat buffer. Fails if the components are nested too deeply, or if the text of a component is too long for a ChatBuf.
+	pub fn to_chat(&self) -> Result<ChatBuf, String> {
+		Container::forest_to_chat(::std::slice::from_ref(self))
+	}
+	
+	/// Converts a list of containers into a flat buffer, with each container at the top level.
+	pub fn forest_to_chat(containers: &[Container]) -> Result<ChatBuf, String> {
+		let mut builder = Builder { buf: ChatBuf::new(), level: 0, len: 0, spans: Vec::new() };
+		
+		for container in containers {
+			builder.container(container, Style::new(), 0, false)?;
+		}
+		
+		Ok(builder.finish())
+	}
+	
+	/// Converts a flat buffer into a list of containers, one for each top level component. This is the inverse of `forest_to_chat`.
+	/// Interactions are attached to the component where they start, which is exact for buffers decoded from JSON.
+	pub fn from_chat(buf: &ChatBuf) -> Vec<Container> {
+		let mut level = 0;
+		
+		let nodes = buf.components().map(|component| {
+			match component.mode() {
+				Mode::Level => (),
+				Mode::Deeper => level += 1,
+				Mode::Shallower(reduction) => level -= ::std::cmp::min(level, reduction as usize)
+			};
+			
+			(level, component)
+		}).collect::<Vec<_>>();
+		
+		let mut position = 0;
+		let mut roots = Vec::new();
+		
+		while position < nodes.len() {
+			roots.push(Container::read(buf, &nodes, &mut position));
+		}
+		
+		roots
+	}
+	
+	/// Reads the component at `position` along with its children, leaving `position` at the next component that is not one of its children.
+	fn read(buf: &ChatBuf, nodes: &[(usize, flat::Component)], position: &mut usize) -> Container {
+		let index = *position;
+		let (level, ref component) = nodes[index];
+		*position += 1;
+		
+		let mut meta = Vec::new();
+		let mut extra = Vec::new();
+		
+		while *position < nodes.len() && nodes[*position].0 > level {
+			let child = *position;
+			let container = Container::read(buf, nodes, position);
+			
+			if nodes[child].1.meta() {
+				meta.push((child, container));
+			} else {
+				extra.push(container);
+			}
+		}
+		
+		let text = component.text().to_owned();
+		
+		let payload = match component.kind() {
+			Kind::Translate => Payload::Translation { translate: text, with: meta.into_iter().map(|(_, container)| container).collect() },
+			Kind::ScoreName => {
+				let part = |kind| meta.iter().find(|&&(child, _)| nodes[child].1.kind() == kind).map(|&(child, _)| nodes[child].1.text().to_owned());
+				
+				Payload::Score { name: text, objective: part(Kind::ScoreObjective).unwrap_or(String::new()), value: part(Kind::ScoreValue) }
+			},
+			Kind::Selector => Payload::Selector(text),
+			Kind::Keybind => Payload::Keybind(text),
+			// Parts of a score outside of a score are read as text.
+			Kind::Text | Kind::ScoreObjective | Kind::ScoreValue => Payload::Text(text)
+		};
+		
+		let interact = buf.own_interaction(index).map(Interaction::from_flat).unwrap_or(Interaction::none());
+		
+		Container::with_extra(Component::new(Segment::new(component.style(), payload), interact), extra)
+	}
+	
+	/// Combines a list of containers into one. Multiple containers are placed after an empty string in an array, which moves them one level deeper.
+	fn from_forest(mut containers: Vec<Container>) -> Container {
+		match containers.len() {
+			0 => Container::Primitive(String::new()),
+			1 => containers.pop().unwrap(),
+			_ => {
+				containers.insert(0, Container::Primitive(String::new()));
+				Container::Array(containers)
+			}
+		}
+	}
+	
+	fn to_value(&self, parent: Style) -> Value {
+		match *self {
+			Container::Primitive(ref text) => Value::String(text.clone()),
+			Container::Array(ref containers) => {
+				let style = self.style(parent);
+				
+				Value::Array(containers.iter().enumerate().map(|(index, container)| container.to_value(if index == 0 { parent } else { style })).collect())
+			},
+			Container::Single { ref component, ref extra } => {
+				let style = component.segment.style;
+				let mut map = Map::new();
+				
+				write_style(&mut map, style, parent);
+				component.interact.write(&mut map);
+				
+				match component.segment.payload {
+					Payload::Text(ref text) => { map.insert(Field::Text.to_str().to_owned(), Value::String(text.clone())); },
+					Payload::Translation { ref translate, ref with } => {
+						map.insert(Field::Translate.to_str().to_owned(), Value::String(translate.clone()));
+						
+						if !with.is_empty() {
+							map.insert(Field::With.to_str().to_owned(), Value::Array(with.iter().map(|arg| arg.to_value(style)).collect()));
+						}
+					},
+					Payload::Score { ref name, ref objective, ref value } => {
+						let mut score = Map::new();
+						score.insert(ScoreField::Name.to_str().to_owned(), Value::String(name.clone()));
+						score.insert(ScoreField::Objective.to_str().to_owned(), Value::String(objective.clone()));
+						
+						if let Some(ref value) = *value {
+							score.insert(ScoreField::Value.to_str().to_owned(), Value::String(value.clone()));
+						}
+						
+						map.insert(Field::Score.to_str().to_owned(), Value::Object(score));
+					},
+					Payload::Selector(ref selector) => { map.insert(Field::Selector.to_str().to_owned(), Value::String(selector.clone())); },
+					Payload::Keybind(ref keybind) => { map.insert(Field::Keybind.to_str().to_owned(), Value::String(keybind.clone())); }
+				}
+				
+				if let Some(ref extra) = *extra {
+					map.insert(Field::Extra.to_str().to_owned(), Value::Array(extra.iter().map(|child| child.to_value(style)).collect()));
+				}
+				
+				Value::Object(map)
+			}
+		}
+	}
+	
+	fn from_value(value: &Value, parent: Style) -> Result<Self, serde_json::Error> {
+		match *value {
+			Value::Array(ref elements) => {
+				let (first, rest) = elements.split_first().ok_or_else(|| serde_json::Error::custom("unexpected empty array of components"))?;
+				
+				// The first element acts as the parent of the rest of the elements.
+				let first = Container::from_value(first, parent)?;
+				let style = first.style(parent);
+				
+				let mut containers = vec![first];
+				
+				for element in rest {
+					containers.push(Container::from_value(element, style)?);
+				}
+				
+				Ok(Container::Array(containers))
+			},
+			Value::Object(ref map) => Container::from_object(map, parent),
+			Value::Null => Err(serde_json::Error::custom("expected a chat component, found null")),
+			_ => Ok(Container::Primitive(StringLike::deserialize(value)?.0))
+		}
+	}
+	
+	fn from_object(map: &Map<String, Value>, parent: Style) -> Result<Self, serde_json::Error> {
+		let mut style = parent;
+		let mut interact = Interaction::none();
+		
+		let mut text = None;
+		let mut translate = None;
+		let mut with = None;
+		let mut score = None;
+		let mut selector = None;
+		let mut keybind = None;
+		let mut extra = None;
+		
+		for (key, value) in map {
+			let field = match Field::from_str(key) {
+				Some(field) => field,
+				None => continue
+			};
+			
+			match field {
+				Field::Bold 			=> style.flags = style.flags.set_bold(BoolLike::deserialize(value)?.0),
+				Field::Italic 			=> style.flags = style.flags.set_italic(BoolLike::deserialize(value)?.0),
+				Field::Underlined 		=> style.flags = style.flags.set_underline(BoolLike::deserialize(value)?.0),
+				Field::Strikethrough 	=> style.flags = style.flags.set_strikethrough(BoolLike::deserialize(value)?.0),
+				Field::Obfuscated 		=> style.flags = style.flags.set_obfuscate(BoolLike::deserialize(value)?.0),
+				Field::Color 			=> if let Some(color) = ColorName::deserialize(value)?.0 { style.color = color },
+				Field::Insertion 		=> interact.insert = Some(StringLike::deserialize(value)?.0),
+				Field::ClickEvent 		=> interact.click = click_event(value)?,
+				Field::HoverEvent 		=> interact.hover = hover_event(value)?,
+				Field::Extra 			=> extra = Some(value),
+				Field::Text 			=> text = Some(StringLike::deserialize(value)?.0),
+				Field::Translate 		=> translate = Some(StringLike::deserialize(value)?.0),
+				Field::With 			=> with = Some(value),
+				Field::Score 			=> score = Some(score_payload(value)?),
+				Field::Selector 		=> selector = Some(StringLike::deserialize(value)?.0),
+				Field::Keybind 			=> keybind = Some(StringLike::deserialize(value)?.0)
+			}
+		}
+		
+		// Children inherit the style of the component, so they can only be decoded once all of the style properties are known.
+		let with = match with {
+			Some(with) => children(with, style)?,
+			None => Vec::new()
+		};
+		
+		let extra = match extra {
+			Some(extra) => {
+				let extra = children(extra, style)?;
+				
+				if extra.is_empty() {
+					return Err(serde_json::Error::custom("unexpected empty array of components"));
+				}
+				
+				extra
+			},
+			None => Vec::new()
+		};
+		
+		let payload = if let Some(text) = text {
+			Payload::Text(text)
+		} else if let Some(translate) = translate {
+			Payload::Translation { translate: translate, with: with }
+		} else if let Some(score) = score {
+			score
+		} else if let Some(selector) = selector {
+			Payload::Selector(selector)
+		} else if let Some(keybind) = keybind {
+			Payload::Keybind(keybind)
+		} else {
+			return Err(serde_json::Error::custom("component has none of the fields text, translate, score, selector, or keybind"));
+		};
+		
+		Ok(Container::with_extra(Component::new(Segment::new(style, payload), interact), extra))
+	}
+}
+
+fn children(value: &Value, parent: Style) -> Result<Vec<Container>, serde_json::Error> {
+	match *value {
+		Value::Array(ref elements) => elements.iter().map(|element| Container::from_value(element, parent)).collect(),
+		_ => Err(serde_json::Error::custom("expected an array of chat components"))
+	}
+}
+
+fn score_payload(value: &Value) -> Result<Payload, serde_json::Error> {
+	let map = match *value {
+		Value::Object(ref map) => map,
+		_ => return Err(serde_json::Error::custom("expected a score object with a name and an objective"))
+	};
+	
+	let mut name = None;
+	let mut objective = None;
+	let mut score = None;
+	
+	for (key, value) in map {
+		match ScoreField::from_str(key) {
+			Some(ScoreField::Name) 		=> name = Some(StringLike::deserialize(value)?.0),
+			Some(ScoreField::Objective) => objective = Some(StringLike::deserialize(value)?.0),
+			Some(ScoreField::Value) 	=> score = Some(StringLike::deserialize(value)?.0),
+			None => ()
+		}
+	}
+	
+	Ok(Payload::Score {
+		name: name.ok_or_else(|| serde_json::Error::missing_field("name"))?,
+		objective: objective.ok_or_else(|| serde_json::Error::missing_field("objective"))?,
+		value: score
+	})
+}
+
+/// Reads the `action` and `value` of an event object.
+fn event_fields(value: &Value) -> Result<(Option<String>, Option<&Value>), serde_json::Error> {
+	let map = match *value {
+		Value::Object(ref map) => map,
+		_ => return Err(serde_json::Error::custom("expected an event object with an action and a value"))
+	};
+	
+	let mut action = None;
+	let mut data = None;
+	
+	for (key, value) in map {
+		match EventField::from_str(key) {
+			Some(EventField::Action) 	=> action = Some(StringLike::deserialize(value)?.0),
+			Some(EventField::Value) 	=> data = Some(value),
+			None => ()
+		}
+	}
+	
+	Ok((action, data))
+}
+
+fn click_event(value: &Value) -> Result<Option<ClickEvent>, serde_json::Error> {
+	match event_fields(value)? {
+		(Some(action), Some(value)) => Ok(Some(ClickEvent::new(&action, StringLike::deserialize(value)?.0))),
+		(_, Some(value)) => { StringLike::deserialize(value)?; Ok(None) },
+		_ => Ok(None)
+	}
+}
+
+fn hover_event(value: &Value) -> Result<Option<HoverEvent>, serde_json::Error> {
+	match event_fields(value)? {
+		(action, Some(value)) => {
+			let value = Container::from_value(value, Style::new())?;
+			
+			Ok(action.map(|action| if action == "show_text" { 
+				HoverEvent::ShowText(Box::new(value)) 
+			} else { 
+				HoverEvent::from_data(&action, value.unformatted()) 
+			}))
+		},
+		_ => Ok(None)
+	}
+}
+
+impl Serialize for Container {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		self.to_value(Style::new()).serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for Container {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		let value = Value::deserialize(deserializer)?;
+		
+		Container::from_value(&value, Style::new()).map_err(D::Error::custom)
+	}
+}
+
+impl Component {
+	pub fn new(segment: Segment, interact: Interaction) -> Self {
+		Component {
+			segment: segment,
+			interact: interact
+		}
+	}
+	
+	pub fn segment(&self) -> &Segment {
+		&self.segment
+	}
+	
+	pub fn style(&self) -> Style {
+		self.segment.style
+	}
+	
+	pub fn payload(&self) -> &Payload {
+		&self.segment.payload
+	}
+	
+	pub fn interaction(&self) -> &Interaction {
+		&self.interact
+	}
+}
+
+impl Segment {
+	pub fn new(style: Style, payload: Payload) -> Self {
+		Segment {
+			style: style,
+			payload: payload
+		}
+	}
+	
+	pub fn style(&self) -> Style {
+		self.style
+	}
+	
+	pub fn payload(&self) -> &Payload {
+		&self.payload
+	}
+}
+
+impl Interaction {
+	/// Converts the interaction to the form used by ChatBuf, where the text of `show_text` events is stored as a ChatBuf.
+	fn to_flat(&self) -> Result<flat::Interaction, String> {
+		Ok(Interaction {
+			insert: self.insert.clone(),
+			click: self.click.clone(),
+			hover: match self.hover {
+				Some(HoverEvent::ShowText(ref text)) => Some(HoverEvent::ShowText(text.to_chat()?)),
+				Some(ref other) => Some(HoverEvent::from_data(other.action(), other.data().unwrap_or("").to_owned())),
+				None => None
+			}
+		})
+	}
+	
+	fn from_flat(interaction: &flat::Interaction) -> Self {
+		Interaction {
+			insert: interaction.insert.clone(),
+			click: interaction.click.clone(),
+			hover: interaction.hover.as_ref().map(|hover| match *hover {
+				HoverEvent::ShowText(ref text) => HoverEvent::ShowText(Box::new(Container::from_forest(Container::from_chat(text)))),
+				ref other => HoverEvent::from_data(other.action(), other.data().unwrap_or("").to_owned())
+			})
+		}
+	}
+	
+	fn write(&self, map: &mut Map<String, Value>) {
+		if let Some(ref insert) = self.insert {
+			map.insert(Field::Insertion.to_str().to_owned(), Value::String(insert.clone()));
+		}
+		
+		if let Some(ref click) = self.click {
+			let mut event = Map::new();
+			event.insert(EventField::Action.to_str().to_owned(), Value::String(click.action().to_owned()));
+			event.insert(EventField::Value.to_str().to_owned(), Value::String(click.value().into_owned()));
+			
+			map.insert(Field::ClickEvent.to_str().to_owned(), Value::Object(event));
+		}
+		
+		if let Some(ref hover) = self.hover {
+			let value = match *hover {
+				HoverEvent::ShowText(ref text) => text.to_value(Style::new()),
+				ref other => Value::String(other.data().unwrap_or("").to_owned())
+			};
+			
+			let mut event = Map::new();
+			event.insert(EventField::Action.to_str().to_owned(), Value::String(hover.action().to_owned()));
+			event.insert(EventField::Value.to_str().to_owned(), value);
+			
+			map.insert(Field::HoverEvent.to_str().to_owned(), Value::Object(event));
+		}
+	}
+}
+
+/// Writes containers to a ChatBuf, keeping track of the current level and the ranges that interactions apply to.
+struct Builder {
+	buf: ChatBuf,
+	level: usize,
+	len: usize,
+	spans: Vec<(Range<usize>, flat::Interaction)>
+}
+
+impl Builder {
+	fn push(&mut self, text: &str, level: usize, meta: bool, kind: Kind, style: Style) -> Result<(), String> {
+		if level > MAX_NESTING as usize {
+			return Err(format!("chat components may only be nested to level {}", MAX_NESTING));
+		}
+		
+		let mode = if level > self.level {
+			Mode::Deeper
+		} else if level == self.level {
+			Mode::Level
+		} else {
+			Mode::Shallower((self.level - level) as u8)
+		};
+		
+		let component = flat::Component::new(text, kind, style, mode, meta)
+			.ok_or_else(|| format!("component text is {} bytes long, which is too long to store", text.len()))?;
+		
+		self.buf.push(component);
+		self.level = level;
+		self.len += 1;
+		
+		Ok(())
+	}
+	
+	/// Writes a container at `level`. Returns the index of the span belonging to the component at the head of the container, if it has an interaction.
+	fn container(&mut self, container: &Container, parent: Style, level: usize, meta: bool) -> Result<Option<usize>, String> {
+		match *container {
+			Container::Primitive(ref text) => {
+				self.push(text, level, meta, Kind::Text, parent)?;
+				
+				Ok(None)
+			},
+			Container::Array(ref containers) => {
+				let (first, rest) = containers.split_first().ok_or_else(|| "unexpected empty array of components".to_owned())?;
+				
+				let head = self.container(first, parent, level, meta)?;
+				let style = first.style(parent);
+				
+				for container in rest {
+					self.container(container, style, level + 1, false)?;
+				}
+				
+				// The rest of the elements are children of the head, so they are covered by its interaction as well.
+				if let Some(head) = head {
+					self.spans[head].0.end = self.len;
+				}
+				
+				Ok(head)
+			},
+			Container::Single { ref component, ref extra } => {
+				let style = component.segment.style;
+				
+				let span = if component.interact.is_none() {
+					None
+				} else {
+					self.spans.push((self.len..self.len, component.interact.to_flat()?));
+					Some(self.spans.len() - 1)
+				};
+				
+				match component.segment.payload {
+					Payload::Text(ref text) => self.push(text, level, meta, Kind::Text, style)?,
+					Payload::Translation { ref translate, ref with } => {
+						self.push(translate, level, meta, Kind::Translate, style)?;
+						
+						for arg in with {
+							self.container(arg, style, level + 1, true)?;
+						}
+					},
+					Payload::Score { ref name, ref objective, ref value } => {
+						self.push(name, level, meta, Kind::ScoreName, style)?;
+						self.push(objective, level + 1, true, Kind::ScoreObjective, style)?;
+						
+						if let Some(ref value) = *value {
+							self.push(value, level + 1, true, Kind::ScoreValue, style)?;
+						}
+					},
+					Payload::Selector(ref selector) => self.push(selector, level, meta, Kind::Selector, style)?,
+					Payload::Keybind(ref keybind) => self.push(keybind, level, meta, Kind::Keybind, style)?
+				}
+				
+				for child in extra.iter().flat_map(|extra| extra.iter()) {
+					self.container(child, style, level + 1, false)?;
+				}
+				
+				if let Some(span) = span {
+					self.spans[span].0.end = self.len;
+				}
+				
+				Ok(span)
+			}
+		}
+	}
+	
+	fn finish(self) -> ChatBuf {
+		let Builder { mut buf, spans, .. } = self;
+		
+		for (range, interaction) in spans {
+			buf.interact(range, interaction);
+		}
+		
+		buf
+	}
+}
+
+#[test]
+fn test_reference_parity() {
+	let sources = [
+		r#""plain""#,
+		r#"[{"text":"a","color":"red","clickEvent":{"action":"run_command","value":"/a"}},"b",["c",1,true,2.5]]"#,
+		r#"{"extra":[{"text":"[Server] "},{"bold":true,"extra":[{"bold":false,"text":"!"}],"text":"Welcome"}],"color":"gold","text":""}"#,
+		r#"{"translate":"chat.type.text","with":[{"insertion":"Steve","clickEvent":{"action":"suggest_command","value":"/tell Steve "},"hoverEvent":{"action":"show_entity","value":{"text":"{name:\"Steve\"}"}},"text":"Steve"},{"text":"hi","hoverEvent":{"action":"show_text","value":["a",{"text":"b","bold":true}]}}],"color":"yellow"}"#,
+		r#"{"score":{"name":"@p","objective":"kills","value":"3"},"italic":"TRUE","extra":[{"selector":"@a"},{"keybind":"key.jump","color":"not_a_color"}]}"#,
+		r#"{"text":"a","clickEvent":{"action":"change_page","value":"2"},"extra":[{"text":"b","insertion":"i","extra":["c"]},"d"]}"#
+	];
+	
+	for source in sources.iter() {
+		let chat = ChatBuf::from_json(source).unwrap();
+		let container = Container::from_json(source).unwrap();
+		
+		// The flat representation must agree with the reference model.
+		assert_eq!(container.to_chat().unwrap(), chat);
+		
+		// Converting from a ChatBuf and back is lossless.
+		assert_eq!(Container::forest_to_chat(&Container::from_chat(&chat)).unwrap(), chat);
+		
+		// Round trip through JSON.
+		assert_eq!(Container::from_json(&container.into_json()).unwrap(), container);
+		assert_eq!(ChatBuf::from_json(&Container::from_chat(&chat)[0].into_json()).unwrap(), chat);
+	}
+	
+	let errors = [
+		"[]", "null", "{}", 
+		r#"{"text":"a","extra":[]}"#, 
+		r#"{"score":{"name":"@p"}}"#, 
+		r#"{"text":"a","with":5}"#, 
+		r#"{"text":"a","clickEvent":"run_command"}"#,
+		r#"{"text":"a","hoverEvent":{"action":"show_text","value":[]}}"#
+	];
+	
+	for source in errors.iter() {
+		assert!(ChatBuf::from_json(source).is_err());
+		assert!(Container::from_json(source).is_err());
+	}
+}
@@ -85,6 +85,7 @@ impl StyleFlags {
 		}
 	}
 	
+	/// Returns the commands that add the flags of `other` that are missing from these flags.
 	pub fn delta_commands(&self, other: StyleFlags) -> DeltaCommands {
 		DeltaCommands {
 			flags: other,
@@ -101,7 +102,7 @@ pub struct Commands {
 
 impl Commands {
 	fn try_next(&mut self) -> Option<Option<StyleCommand>> {
-		if self.flag == 6 {return None};
+		if self.flag == 5 {return None};
 		self.flag += 1;
 		
 		Some(match self.flag {
@@ -137,7 +138,7 @@ pub struct DeltaCommands {
 
 impl DeltaCommands {
 	fn try_next(&mut self) -> Option<Option<StyleCommand>> {
-		if self.flag == 6 {return None};
+		if self.flag == 5 {return None};
 		self.flag += 1;
 		
 		Some(match self.flag {
@@ -190,22 +191,28 @@ impl Style {
 		}
 	}
 	
+	/// Replaces an RGB color with the nearest palette color, so that the style can be written with § codes.
+	pub fn to_legacy(&self) -> Self {
+		Style {
+			color: self.color.to_legacy(),
+			flags: self.flags
+		}
+	}
+	
 	pub fn will_reset(&self, other: Self) -> bool {
 		// If any style flags are unset, we have to reencode the color as well.
 		self.color != other.color || self.flags.or(other.flags) != other.flags
 	}
 	
+	/// Returns the shortest sequence of commands that changes this style into the other style.
+	/// Flags can only be removed by a color code or a reset, so when one is removed, every flag has to be added again.
 	pub fn transition(&self, other: Style) -> Transition {
 		if *self == other {
 			Transition::None
 		} else if self.will_reset(other) {
 			Transition::Reset(iter::once(other.color.command()).chain(other.flags.commands()))
 		} else {
-			/*for command in self.flags.delta_commands(other.flags).filter_map(|x| x) {
-				self.write_command(command)?;
-			}*/
-			
-			unimplemented!()
+			Transition::Delta(self.flags.delta_commands(other.flags))
 		}
 	}
 }
@@ -232,18 +239,59 @@ impl Iterator for Transition {
 pub enum Color {
 	Default,
 	Palette(PaletteColor),
-	//Rgb(u8, u8, u8)
+	/// A 24 bit color, which can only be sent in JSON chat components. It is replaced with the nearest palette color when written with § codes.
+	/// The channels are stored as bytes instead of an `Rgb` to keep styles small.
+	Rgb(u8, u8, u8)
 }
 
 impl Color {
+	/// Returns the command that switches to this color. RGB colors switch to the nearest palette color.
 	pub fn command(&self) -> StyleCommand {
 		match *self {
 			Color::Default => StyleCommand::Reset,
-			Color::Palette(pal) => StyleCommand::Color(pal)
+			Color::Palette(pal) => StyleCommand::Color(pal),
+			Color::Rgb(r, g, b) => StyleCommand::Color(PaletteColor::nearest(Rgb::new(r, g, b)))
+		}
+	}
+	
+	/// Replaces an RGB color with the nearest palette color. Other colors are returned as they are.
+	pub fn to_legacy(&self) -> Self {
+		match *self {
+			Color::Rgb(r, g, b) => Color::Palette(PaletteColor::nearest(Rgb::new(r, g, b))),
+			color => color
+		}
+	}
+	
+	/// Parses the value of the `color` field of a JSON chat component. The name `reset` maps to the default color, and RGB colors are written as `#RRGGBB`.
+	/// Like the vanilla client, the hex digits after `#` are read as any signed 32-bit integer, such as `#ff800` or `#-1`, and only the low 24 bits are used.
+	pub fn from_name(name: &str) -> Option<Self> {
+		if name == "reset" {
+			Some(Color::Default)
+		} else if name.starts_with('#') {
+			i32::from_str_radix(&name[1..], 16).ok().map(|rgb| Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+		} else {
+			PaletteColor::from_name(name).map(Color::Palette)
+		}
+	}
+	
+	/// Returns the name used for this color in the `color` field of JSON chat components.
+	pub fn name(&self) -> String {
+		match *self {
+			Color::Default => "reset".to_owned(),
+			Color::Palette(pal) => pal.name().to_owned(),
+			Color::Rgb(r, g, b) => Rgb::new(r, g, b).to_string()
 		}
 	}
 }
 
+/// Every palette color, in the order of their codes.
+const PALETTE: [PaletteColor; 16] = [
+	PaletteColor::Black, PaletteColor::DarkBlue, PaletteColor::DarkGreen, PaletteColor::DarkAqua,
+	PaletteColor::DarkRed, PaletteColor::DarkPurple, PaletteColor::Gold, PaletteColor::Gray,
+	PaletteColor::DarkGray, PaletteColor::Blue, PaletteColor::Green, PaletteColor::Aqua,
+	PaletteColor::Red, PaletteColor::LightPurple, PaletteColor::Yellow, PaletteColor::White
+];
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum PaletteColor {
 	Black,
@@ -307,6 +355,18 @@ impl PaletteColor {
 		})
 	}
 	
+	/// Returns the palette color with the foreground color closest to the RGB color.
+	pub fn nearest(rgb: Rgb) -> Self {
+		let distance = |pal: &PaletteColor| {
+			let other = pal.foreground();
+			let channel = |a: u8, b: u8| (a as i32 - b as i32) * (a as i32 - b as i32);
+			
+			channel(rgb.r(), other.r()) + channel(rgb.g(), other.g()) + channel(rgb.b(), other.b())
+		};
+		
+		*PALETTE.iter().min_by_key(|pal| distance(pal)).unwrap()
+	}
+	
 	fn from_code(code: char) -> Option<Self> {
 		Some(match code {
 			'0' => PaletteColor::Black,
@@ -329,6 +389,49 @@ impl PaletteColor {
 		})
 	}
 	
+	pub fn from_name(name: &str) -> Option<Self> {
+		Some(match name {
+			"black" => PaletteColor::Black,
+			"dark_blue" => PaletteColor::DarkBlue,
+			"dark_green" => PaletteColor::DarkGreen,
+			"dark_aqua" => PaletteColor::DarkAqua,
+			"dark_red" => PaletteColor::DarkRed,
+			"dark_purple" => PaletteColor::DarkPurple,
+			"gold" => PaletteColor::Gold,
+			"gray" => PaletteColor::Gray,
+			"dark_gray" => PaletteColor::DarkGray,
+			"blue" => PaletteColor::Blue,
+			"green" => PaletteColor::Green,
+			"aqua" => PaletteColor::Aqua,
+			"red" => PaletteColor::Red,
+			"light_purple" => PaletteColor::LightPurple,
+			"yellow" => PaletteColor::Yellow,
+			"white" => PaletteColor::White,
+			_ => return None
+		})
+	}
+	
+	pub fn name(&self) -> &'static str {
+		match *self {
+			PaletteColor::Black 		=> "black",
+			PaletteColor::DarkBlue 	=> "dark_blue",
+			PaletteColor::DarkGreen	=> "dark_green",
+			PaletteColor::DarkAqua 	=> "dark_aqua",
+			PaletteColor::DarkRed 		=> "dark_red",
+			PaletteColor::DarkPurple 	=> "dark_purple",
+			PaletteColor::Gold 		=> "gold",
+			PaletteColor::Gray 		=> "gray",
+			PaletteColor::DarkGray 	=> "dark_gray",
+			PaletteColor::Blue 		=> "blue",
+			PaletteColor::Green 		=> "green",
+			PaletteColor::Aqua 		=> "aqua",
+			PaletteColor::Red 			=> "red",
+			PaletteColor::LightPurple 	=> "light_purple",
+			PaletteColor::Yellow 		=> "yellow",
+			PaletteColor::White 		=> "white"
+		}
+	}
+	
 	fn as_code(&self) -> char {
 		match *self {
 			PaletteColor::Black 		=> '0',